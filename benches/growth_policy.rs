@@ -0,0 +1,145 @@
+//! Amortized cost of each growth policy on `MyVec` and `ArrayList`.
+//!
+//! `cargo bench --bench growth_policy`
+//!
+//! Copies are counted as the items a realloc has to move in the worst
+//! case, so they do not depend on whether the system allocator managed to
+//! grow the block in place. `MyVec` reports them through a `CountingAlloc`;
+//! `ArrayList` always uses the global allocator, so its row is worked out
+//! from the capacity changes instead: every resize moves the current items.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use playground_dsa_rust::{
+    ArrayList, CountingAlloc, Doubling, FixedIncrement, GrowthPolicy, MyVec, OneAndHalf,
+    ShrinkOnUnderflow,
+};
+
+const PUSHES: u64 = 1_000_000;
+
+struct Row {
+    grows: usize,
+    items_copied: usize,
+    peak_items: usize,
+    pushed: Duration,
+    shrinks: usize,
+    popped: Duration,
+}
+
+fn print(name: &str, row: Row) {
+    println!(
+        "{:<32} {:>8} {:>14} {:>12.3} {:>12} {:>10.1?} {:>8} {:>10.1?}",
+        name,
+        row.grows,
+        row.items_copied,
+        row.items_copied as f64 / PUSHES as f64,
+        row.peak_items,
+        row.pushed,
+        row.shrinks,
+        row.popped,
+    );
+}
+
+fn my_vec<G: GrowthPolicy>(growth: G) -> Row {
+    let counter = CountingAlloc::new();
+    let start = Instant::now();
+    let mut vec = MyVec::with_growth_in(growth, &counter);
+    for i in 0..PUSHES {
+        vec.push(black_box(i));
+    }
+    let pushed = start.elapsed();
+    let grow = counter.stats();
+
+    // no reset: the vector still holds its block, and freeing it after a
+    // reset would take it out of 0 bytes in use
+    let start = Instant::now();
+    while black_box(vec.pop()).is_some() {}
+    let popped = start.elapsed();
+    let shrinks = counter.stats().reallocations - grow.reallocations;
+
+    let size = std::mem::size_of::<u64>();
+    Row {
+        grows: grow.reallocations,
+        items_copied: grow.bytes_moved / size,
+        peak_items: grow.peak_bytes_in_use / size,
+        pushed,
+        shrinks,
+        popped,
+    }
+}
+
+fn array_list<G: GrowthPolicy + std::fmt::Debug>(growth: G) -> Row {
+    let mut list = ArrayList::with_growth(0, growth);
+    let mut capacity = list.capacity();
+    let (mut grows, mut items_copied) = (0, 0);
+    let start = Instant::now();
+    for i in 0..PUSHES {
+        list.append(black_box(i));
+        if list.capacity() != capacity {
+            // the first allocation is not a realloc, like for MyVec; later
+            // ones move the items that were there before this one
+            if capacity != 0 {
+                grows += 1;
+                items_copied += i as usize;
+            }
+            capacity = list.capacity();
+        }
+    }
+    let pushed = start.elapsed();
+    let peak_items = capacity;
+
+    let mut shrinks = 0;
+    let start = Instant::now();
+    for _ in 0..PUSHES {
+        black_box(list.pop());
+        if list.capacity() != capacity {
+            shrinks += 1;
+            capacity = list.capacity();
+        }
+    }
+    let popped = start.elapsed();
+
+    Row {
+        grows,
+        items_copied,
+        peak_items,
+        pushed,
+        shrinks,
+        popped,
+    }
+}
+
+fn main() {
+    println!("{PUSHES} pushes of u64, then pop until empty");
+    println!(
+        "{:<32} {:>8} {:>14} {:>12} {:>12} {:>10} {:>8} {:>10}",
+        "policy",
+        "grows",
+        "items copied",
+        "copies/push",
+        "peak items",
+        "push time",
+        "shrinks",
+        "pop time",
+    );
+    print("MyVec Doubling", my_vec(Doubling));
+    print("MyVec OneAndHalf", my_vec(OneAndHalf));
+    print("MyVec FixedIncrement(1024)", my_vec(FixedIncrement(1024)));
+    print(
+        "MyVec ShrinkOnUnderflow",
+        my_vec(ShrinkOnUnderflow(Doubling)),
+    );
+    print("ArrayList Doubling", array_list(Doubling));
+    print("ArrayList OneAndHalf", array_list(OneAndHalf));
+    print(
+        "ArrayList FixedIncrement(1024)",
+        array_list(FixedIncrement(1024)),
+    );
+    print(
+        "ArrayList ShrinkOnUnderflow",
+        array_list(ShrinkOnUnderflow(Doubling)),
+    );
+}
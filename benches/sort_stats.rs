@@ -0,0 +1,96 @@
+//! Operation counts instead of timings: comparisons, swaps, writes and
+//! allocations for every sorter, then the three in place merges on
+//! differently shaped runs.
+//!
+//! `cargo bench --features sort-stats --bench sort_stats`
+//!
+//! Doubling n shows the growth: about 4x per row for O(n²), a bit over 2x
+//! for O(n log n).
+
+use playground_dsa_rust::{
+    Counted, CountingGlobalAlloc, SortStats, counting, measure, merge_inplace_insertion_by,
+    merge2_by, merge3_by, sort_stats, sorters,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+#[global_allocator]
+static ALLOC: CountingGlobalAlloc = CountingGlobalAlloc;
+
+const SIZES: [usize; 3] = [1_000, 2_000, 4_000];
+
+type Merge = fn(&mut [u64], usize, &mut dyn FnMut(&u64, &u64) -> std::cmp::Ordering);
+
+fn print(name: &str, n: usize, s: SortStats) {
+    println!(
+        "{:<30} {:>6} {:>12} {:>10} {:>12} {:>6} {:>10}",
+        name, n, s.comparisons, s.swaps, s.writes, s.allocations, s.allocated_bytes
+    );
+}
+
+fn header(first: &str) {
+    println!(
+        "{:<30} {:>6} {:>12} {:>10} {:>12} {:>6} {:>10}",
+        first, "n", "comparisons", "swaps", "writes", "allocs", "bytes"
+    );
+}
+
+// two sorted runs, shaped by name
+fn runs(shape: &str, n: usize, rng: &mut StdRng) -> (Vec<u64>, usize) {
+    let n64 = n as u64;
+    let (mut v, mid): (Vec<u64>, usize) = match shape {
+        "random" => ((0..n).map(|_| rng.gen_range(0, n64)).collect(), n / 2),
+        "interleaved" => (
+            (0..n64).step_by(2).chain((1..n64).step_by(2)).collect(),
+            n.div_ceil(2),
+        ),
+        "in order" => ((0..n64).collect(), n / 2),
+        "swapped halves" => ((n64 / 2..n64).chain(0..n64 / 2).collect(), n - n / 2),
+        "short left run" => {
+            let left = (0..8).map(|_| rng.gen_range(0, n64)).collect::<Vec<_>>();
+            (left.into_iter().chain(0..n64 - 8).collect(), 8)
+        }
+        _ => unreachable!(),
+    };
+    v[..mid].sort();
+    v[mid..].sort();
+    (v, mid)
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(50);
+
+    header("sorter (random input)");
+    for sorter in sorters::<Counted<u64>>() {
+        for n in SIZES {
+            let input: Vec<u64> = (0..n).map(|_| rng.r#gen()).collect();
+            print(sorter.name(), n, sort_stats(sorter.as_ref(), &input));
+        }
+    }
+
+    let merges: [(&str, Merge); 3] = [
+        ("merge2", |v, mid, cmp| merge2_by(v, mid, cmp)),
+        ("merge3", |v, mid, cmp| merge3_by(v, mid, cmp)),
+        ("merge_inplace_insertion", |v, mid, cmp| {
+            merge_inplace_insertion_by(v, mid, cmp)
+        }),
+    ];
+    for shape in [
+        "random",
+        "interleaved",
+        "in order",
+        "swapped halves",
+        "short left run",
+    ] {
+        println!();
+        header(&format!("merge ({shape})"));
+        for (name, merge) in merges {
+            for n in SIZES {
+                let (mut v, mid) = runs(shape, n, &mut rng);
+                let mut cmp = counting(u64::cmp);
+                let (_, stats) = measure(|| merge(&mut v, mid, &mut cmp));
+                assert!(v.is_sorted(), "{name} on {shape}");
+                print(name, n, stats);
+            }
+        }
+    }
+}
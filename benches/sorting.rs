@@ -0,0 +1,76 @@
+//! Every sorter in the registry on random, sorted and reversed input.
+//!
+//! `cargo bench --bench sorting`
+//! `cargo bench --bench sorting -- quicksort merge_sort`
+//!
+//! Extra arguments pick sorters by name, without any every sorter runs.
+//! Sorters with a quadratic worst case only get the small size, quicksort
+//! included since sorted input is its worst case.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use playground_dsa_rust::{BigO, Sorter, sorter, sorters};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const SMALL: usize = 2_000;
+const LARGE: usize = 100_000;
+
+// random, sorted, reversed
+fn inputs(n: usize) -> [Vec<u64>; 3] {
+    let mut rng = StdRng::seed_from_u64(n as u64);
+    let random = (0..n).map(|_| rng.gen_range(0, n as u64)).collect();
+    let sorted = (0..n as u64).collect();
+    let reversed = (0..n as u64).rev().collect();
+    [random, sorted, reversed]
+}
+
+fn time(sorter: &dyn Sorter<u64>, input: &[u64]) -> Duration {
+    let mut v = input.to_vec();
+    let start = Instant::now();
+    sorter.sort(black_box(&mut v));
+    let elapsed = start.elapsed();
+    assert!(v.is_sorted(), "{} did not sort", sorter.name());
+    elapsed
+}
+
+fn main() {
+    let names: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let picked: Vec<Box<dyn Sorter<u64>>> = if names.is_empty() {
+        sorters()
+    } else {
+        names
+            .iter()
+            .map(|name| sorter(name).unwrap_or_else(|| panic!("no sorter named {name}")))
+            .collect()
+    };
+
+    println!(
+        "{:<30} {:>6} {:>8} {:>12} {:>8} {:>12} {:>12} {:>12}",
+        "sorter", "stable", "in place", "average", "n", "random", "sorted", "reversed"
+    );
+    for s in &picked {
+        for n in [SMALL, LARGE] {
+            if n == LARGE && s.complexity().worst == BigO::Quadratic {
+                continue;
+            }
+            let times = inputs(n).map(|input| time(s.as_ref(), &input));
+            println!(
+                "{:<30} {:>6} {:>8} {:>12} {:>8} {:>12.1?} {:>12.1?} {:>12.1?}",
+                s.name(),
+                s.is_stable(),
+                s.is_in_place(),
+                s.complexity().average,
+                n,
+                times[0],
+                times[1],
+                times[2]
+            );
+        }
+    }
+}
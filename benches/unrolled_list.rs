@@ -0,0 +1,151 @@
+//! `UnrolledList` against the pointer per item `LinkedList` and the
+//! contiguous `ArrayList` and `MyVec`.
+//!
+//! `cargo bench --bench unrolled_list`
+//!
+//! Indices for get and insert come from a seeded rng, so every structure
+//! sees the same ones. `LinkedList` has no insert in the middle.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use playground_dsa_rust::{ArrayList, LinkedList, MyVec, UnrolledList};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const ITEMS: u64 = 100_000;
+const LOOKUPS: usize = 1_000;
+
+struct Timings {
+    append: Duration,
+    iterate: Duration,
+    get: Duration,
+    insert: Option<Duration>,
+}
+
+fn indices(seed: u64) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..LOOKUPS)
+        .map(|_| rng.gen_range(0, ITEMS as usize))
+        .collect()
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn linked_list() -> Timings {
+    let mut list = LinkedList::new();
+    let append = time(|| (0..ITEMS).for_each(|i| list.push_back(black_box(i))));
+    let iterate = time(|| {
+        black_box(list.iter().map(|item| *item).sum::<u64>());
+    });
+    let get = time(|| {
+        for i in indices(1) {
+            black_box(*list.get(i));
+        }
+    });
+    Timings {
+        append,
+        iterate,
+        get,
+        insert: None,
+    }
+}
+
+fn array_list() -> Timings {
+    let mut list = ArrayList::new(0);
+    let append = time(|| (0..ITEMS).for_each(|i| list.append(black_box(i))));
+    let iterate = time(|| {
+        black_box(list.as_slice().iter().sum::<u64>());
+    });
+    let get = time(|| {
+        for i in indices(1) {
+            black_box(list.get(i));
+        }
+    });
+    let insert = time(|| {
+        for i in indices(2) {
+            list.insert_at(black_box(0), i);
+        }
+    });
+    Timings {
+        append,
+        iterate,
+        get,
+        insert: Some(insert),
+    }
+}
+
+fn my_vec() -> Timings {
+    let mut vec = MyVec::new();
+    let append = time(|| (0..ITEMS).for_each(|i| vec.push(black_box(i))));
+    let iterate = time(|| {
+        black_box(vec.as_slice().iter().sum::<u64>());
+    });
+    let get = time(|| {
+        for i in indices(1) {
+            black_box(vec.get(i));
+        }
+    });
+    let insert = time(|| {
+        for i in indices(2) {
+            vec.insert(i, black_box(0));
+        }
+    });
+    Timings {
+        append,
+        iterate,
+        get,
+        insert: Some(insert),
+    }
+}
+
+fn unrolled_list<const B: usize>() -> Timings {
+    let mut list = UnrolledList::<u64, B>::new();
+    let append = time(|| (0..ITEMS).for_each(|i| list.push_back(black_box(i))));
+    let iterate = time(|| {
+        black_box(list.iter().sum::<u64>());
+    });
+    let get = time(|| {
+        for i in indices(1) {
+            black_box(list.get(i));
+        }
+    });
+    let insert = time(|| {
+        for i in indices(2) {
+            list.insert(i, black_box(0));
+        }
+    });
+    Timings {
+        append,
+        iterate,
+        get,
+        insert: Some(insert),
+    }
+}
+
+fn print(name: &str, t: Timings) {
+    let insert = t.insert.map_or("-".to_string(), |d| format!("{d:.1?}"));
+    println!(
+        "{:<20} {:>12.1?} {:>12.1?} {:>12.1?} {:>12}",
+        name, t.append, t.iterate, t.get, insert
+    );
+}
+
+fn main() {
+    println!("{ITEMS} u64 items, {LOOKUPS} random gets and inserts");
+    println!(
+        "{:<20} {:>12} {:>12} {:>12} {:>12}",
+        "structure", "append", "iterate", "get", "insert"
+    );
+    print("LinkedList", linked_list());
+    print("ArrayList", array_list());
+    print("MyVec", my_vec());
+    print("UnrolledList<16>", unrolled_list::<16>());
+    print("UnrolledList<64>", unrolled_list::<64>());
+    print("UnrolledList<256>", unrolled_list::<256>());
+}
@@ -1,12 +1,46 @@
-fn bubble_sorting(v: &mut Vec<i32>) -> Vec<i32> {
-    for i in 0..v.len() - 1 {
+use std::cmp::Ordering;
+
+use super::instrument;
+
+/*
+* Bubble sort
+* input: [5, 4, 1, 2, 3]
+* output: [1, 2, 3, 4, 5]
+*
+* every pass bubbles the largest unsorted item to the end, a pass
+* without a swap means the slice is sorted
+* O(n²) comparisons and swaps, O(n) on sorted input, stable
+* */
+pub fn bubble_sorting<T: Ord>(v: &mut [T]) {
+    bubble_sorting_by(v, T::cmp);
+}
+
+pub fn bubble_sorting_by<T, F>(v: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 0..v.len().saturating_sub(1) {
+        let mut swapped = false;
         for j in 0..v.len() - 1 - i {
-            if v[j] > v[j + 1] {
+            // only strictly greater items move, so equal ones keep their order
+            if cmp(&v[j], &v[j + 1]) == Ordering::Greater {
                 v.swap(j, j + 1);
+                instrument::swapped();
+                swapped = true;
             }
         }
+        if !swapped {
+            break;
+        }
     }
-    v.to_vec()
+}
+
+pub fn bubble_sorting_by_key<T, K, F>(v: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    bubble_sorting_by(v, |a, b| f(a).cmp(&f(b)));
 }
 
 #[cfg(test)]
@@ -16,6 +50,20 @@ mod tests {
     #[test]
     fn test_bubble_sorting() {
         let mut arr = vec![5, 4, 1, 2, 3];
-        assert_eq!(bubble_sorting(&mut arr), vec![1, 2, 3, 4, 5]);
+        bubble_sorting(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5]);
+
+        let mut empty: [i32; 0] = [];
+        bubble_sorting(&mut empty);
+    }
+
+    #[test]
+    fn sorts_records_by_key() {
+        let mut people = vec![("ana", 31), ("bo", 25), ("cy", 40), ("di", 19)];
+        bubble_sorting_by_key(&mut people, |p| p.1);
+        assert_eq!(people, [("di", 19), ("bo", 25), ("ana", 31), ("cy", 40)]);
+
+        bubble_sorting_by(&mut people, |a, b| b.0.cmp(a.0));
+        assert_eq!(people, [("di", 19), ("cy", 40), ("bo", 25), ("ana", 31)]);
     }
 }
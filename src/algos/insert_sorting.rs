@@ -1,14 +1,40 @@
-fn insert_sort(arr: &mut Vec<i32>) -> Vec<i32> {
-    let size = arr.len();
-
-    for i in 1..size {
-        for j in 0..i {
-            if arr[i] < arr[j] {
-                arr.swap(i, j);
-            }
+use std::cmp::Ordering;
+
+use super::instrument;
+
+/*
+* Insertion sort
+* input: [5, 4, 1, 2, 3]
+* output: [1, 2, 3, 4, 5]
+*
+* v[..i] is always sorted, v[i] is swapped down until the item before
+* it is not greater
+* O(n²) comparisons and swaps, O(n) on sorted input, stable
+* */
+pub fn insert_sort<T: Ord>(arr: &mut [T]) {
+    insert_sort_by(arr, T::cmp);
+}
+
+pub fn insert_sort_by<T, F>(arr: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 1..arr.len() {
+        let mut j = i;
+        while j > 0 && cmp(&arr[j - 1], &arr[j]) == Ordering::Greater {
+            arr.swap(j - 1, j);
+            instrument::swapped();
+            j -= 1;
         }
     }
-    arr.to_vec()
+}
+
+pub fn insert_sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    insert_sort_by(arr, |a, b| f(a).cmp(&f(b)));
 }
 
 #[cfg(test)]
@@ -18,6 +44,17 @@ mod tests {
     #[test]
     fn test_insert_sort() {
         let mut arr = vec![5, 4, 1, 2, 3];
-        assert_eq!(insert_sort(&mut arr), vec![1, 2, 3, 4, 5]);
+        insert_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn sorts_records_by_key() {
+        let mut words = vec!["pear", "fig", "banana", "kiwis", "yo"];
+        insert_sort_by_key(&mut words, |w| w.len());
+        assert_eq!(words, ["yo", "fig", "pear", "kiwis", "banana"]);
+
+        insert_sort_by(&mut words, |a, b| b.cmp(a));
+        assert_eq!(words, ["yo", "pear", "kiwis", "fig", "banana"]);
     }
 }
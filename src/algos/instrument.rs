@@ -0,0 +1,325 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    cmp::Ordering,
+    fmt,
+    ops::AddAssign,
+};
+
+use super::Sorter;
+
+/*
+ * Instrumentation
+ * counts what a sort does while it runs inside `measure`:
+ *
+ *   comparisons  Counted<T> elements, or a comparator wrapped by counting()
+ *   swaps        the sorts report each slice::swap through swapped()
+ *   writes       every item written into a slot: 2 per swap, the items a
+ *                rotation shifts, the items merge2 moves out and back, and
+ *                every clone of a Counted<T>
+ *   allocations  CountingGlobalAlloc, when it is the #[global_allocator]
+ *
+ * moves in Rust are plain memcpys that no wrapper can see, so swaps and
+ * the writes besides clones are reported by the sorts themselves. Those
+ * hooks only count with the `sort-stats` feature (and in this crate's
+ * unit tests), otherwise they compile to nothing and swaps stays 0:
+ *
+ *   cargo bench --features sort-stats --bench sort_stats
+ *
+ * The counters are thread local and only tick inside `measure`, outside
+ * of it they cost one thread local read. The allocation tests live in
+ * tests/sort_stats.rs, which installs the allocator for its own binary.
+ * */
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SortStats {
+    pub comparisons: usize,
+    pub swaps: usize,
+    pub writes: usize,
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+}
+
+impl AddAssign for SortStats {
+    fn add_assign(&mut self, other: Self) {
+        self.comparisons += other.comparisons;
+        self.swaps += other.swaps;
+        self.writes += other.writes;
+        self.allocations += other.allocations;
+        self.allocated_bytes += other.allocated_bytes;
+    }
+}
+
+impl fmt::Display for SortStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} comparisons, {} swaps, {} writes, {} allocations ({} bytes)",
+            self.comparisons, self.swaps, self.writes, self.allocations, self.allocated_bytes
+        )
+    }
+}
+
+thread_local! {
+    // None outside of measure
+    static STATS: Cell<Option<SortStats>> = const { Cell::new(None) };
+}
+
+fn record(f: impl FnOnce(&mut SortStats)) {
+    // try_with: the allocator can still run while the thread is torn down
+    let _ = STATS.try_with(|cell| {
+        if let Some(mut stats) = cell.get() {
+            f(&mut stats);
+            cell.set(Some(stats));
+        }
+    });
+}
+
+/// Runs `f` and returns what it counted. Nested calls are also added to
+/// the outer one.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, SortStats) {
+    // puts the outer counters back even if f panics
+    struct Restore(Option<SortStats>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let inner = STATS.replace(self.0);
+            if let Some(inner) = inner {
+                record(|stats| *stats += inner);
+            }
+        }
+    }
+
+    let _restore = Restore(STATS.replace(Some(SortStats::default())));
+    let result = f();
+    (result, STATS.get().unwrap_or_default())
+}
+
+/// Sorts a copy of `input` wrapped in [`Counted`], so comparisons and
+/// clones are counted for any sorter.
+pub fn sort_stats<T: Ord + Clone>(sorter: &dyn Sorter<Counted<T>>, input: &[T]) -> SortStats {
+    let mut items: Vec<Counted<T>> = input.iter().cloned().map(Counted).collect();
+    measure(|| sorter.sort(&mut items)).1
+}
+
+/// Wraps a comparator for the `_by` functions so its calls are counted.
+pub fn counting<T, F>(mut cmp: F) -> impl FnMut(&T, &T) -> Ordering
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    move |a, b| {
+        record(|stats| stats.comparisons += 1);
+        cmp(a, b)
+    }
+}
+
+/// An item whose comparisons and clones are counted.
+#[derive(Debug, Default)]
+pub struct Counted<T>(pub T);
+
+impl<T: Clone> Clone for Counted<T> {
+    fn clone(&self) -> Self {
+        record(|stats| stats.writes += 1);
+        Counted(self.0.clone())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Counted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        record(|stats| stats.comparisons += 1);
+        self.0 == other.0
+    }
+}
+
+impl<T: Eq> Eq for Counted<T> {}
+
+impl<T: Ord> PartialOrd for Counted<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Counted<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        record(|stats| stats.comparisons += 1);
+        self.0.cmp(&other.0)
+    }
+}
+
+// whether the sorts report their swaps and moves
+const HOOKS: bool = cfg!(any(test, feature = "sort-stats"));
+
+/// Counts one swap (two writes) the calling sort just made.
+#[inline(always)]
+pub(crate) fn swapped() {
+    if HOOKS {
+        record(|stats| {
+            stats.swaps += 1;
+            stats.writes += 2;
+        });
+    }
+}
+
+/// Counts items the calling sort just moved by rotating or copying.
+#[inline(always)]
+pub(crate) fn moved(items: usize) {
+    if HOOKS {
+        record(|stats| stats.writes += items);
+    }
+}
+
+/// Forwards to the system allocator and counts the allocations (and
+/// reallocations) made inside [`measure`] on the same thread.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOC: CountingGlobalAlloc = CountingGlobalAlloc;
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingGlobalAlloc;
+
+impl CountingGlobalAlloc {
+    fn count(size: usize) {
+        record(|stats| {
+            stats.allocations += 1;
+            stats.allocated_bytes += size;
+        });
+    }
+}
+
+// SAFETY: forwards every call to System unchanged, counting only touches a
+// const initialised thread local, which never allocates
+unsafe impl GlobalAlloc for CountingGlobalAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BigO, bubble_sorting, merge_inplace_insertion_by, merge2_by, merge3_by, sorters};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    // [0, 2, 4, ..] ++ [1, 3, 5, ..]: every right item belongs between two
+    // left ones, the worst case for the rotating merges
+    fn interleaved(n: usize) -> Vec<usize> {
+        (0..n).step_by(2).chain((1..n).step_by(2)).collect()
+    }
+
+    fn merge_stats(merge: fn(&mut [usize], usize), mut v: Vec<usize>, mid: usize) -> SortStats {
+        let (_, stats) = measure(|| merge(&mut v, mid));
+        assert!(v.is_sorted());
+        stats
+    }
+
+    fn merge2_counted(v: &mut [usize], mid: usize) {
+        merge2_by(v, mid, counting(usize::cmp));
+    }
+
+    fn merge3_counted(v: &mut [usize], mid: usize) {
+        merge3_by(v, mid, counting(usize::cmp));
+    }
+
+    fn insertion_counted(v: &mut [usize], mid: usize) {
+        merge_inplace_insertion_by(v, mid, counting(usize::cmp));
+    }
+
+    #[test]
+    fn counts_a_small_bubble_sort() {
+        let mut v = vec![Counted(3), Counted(2), Counted(1)];
+        let ((), stats) = measure(|| bubble_sorting(&mut v));
+        let expected = SortStats {
+            comparisons: 3,
+            swaps: 3,
+            writes: 6,
+            ..SortStats::default()
+        };
+        assert_eq!(stats, expected);
+
+        // the inner counts also land in the outer measure
+        let (_, outer) = measure(|| measure(|| v.clone()).1);
+        assert_eq!(outer.writes, 3);
+    }
+
+    #[test]
+    fn merge3_moves_grow_quadratically() {
+        let n = if cfg!(miri) { 64 } else { 1024 };
+        let small = merge_stats(merge3_counted, interleaved(n), n.div_ceil(2));
+        let large = merge_stats(merge3_counted, interleaved(2 * n), n);
+
+        // doubling n quadruples the writes, the comparisons only double
+        let writes = large.writes as f64 / small.writes as f64;
+        let comparisons = large.comparisons as f64 / small.comparisons as f64;
+        assert!((3.5..4.5).contains(&writes), "writes grew {writes}x");
+        assert!(
+            (1.8..2.2).contains(&comparisons),
+            "comparisons grew {comparisons}x"
+        );
+
+        // one rotation of about n / 2 items per right item
+        assert!(small.writes >= n * n / 8, "{small}");
+    }
+
+    #[test]
+    fn merge_inplace_insertion_against_merge2() {
+        let n = if cfg!(miri) { 256 } else { 1024 };
+
+        // interleaved runs: the binary searches lose to merge2's single
+        // scan, and every left item needs its own rotation
+        let buffered = merge_stats(merge2_counted, interleaved(n), n / 2);
+        let rotated = merge_stats(insertion_counted, interleaved(n), n / 2);
+        assert_eq!(buffered.writes, n + n / 2);
+        assert!(rotated.writes > n * n / 10, "{rotated} vs {buffered}");
+
+        // a short left run: binary search beats the linear scan
+        let mut v: Vec<usize> = vec![n / 4, n / 2, 3 * n / 4, n];
+        v.extend(0..n);
+        let buffered = merge_stats(merge2_counted, v.clone(), 4);
+        let rotated = merge_stats(insertion_counted, v, 4);
+        assert!(
+            rotated.comparisons * 4 < buffered.comparisons,
+            "{rotated} vs {buffered}"
+        );
+
+        // already in order: nothing moves, but merge2 still copies
+        let sorted: Vec<usize> = (0..n).collect();
+        let buffered = merge_stats(merge2_counted, sorted.clone(), n / 2);
+        let rotated = merge_stats(insertion_counted, sorted, n / 2);
+        assert_eq!(rotated.writes, 0);
+        assert_eq!(buffered.writes, n + n / 2);
+    }
+
+    #[test]
+    fn sorters_match_their_declared_complexity() {
+        let (n, m) = if cfg!(miri) { (32, 128) } else { (256, 1024) };
+        let mut rng = StdRng::seed_from_u64(50);
+        let small: Vec<u32> = (0..n).map(|_| rng.r#gen()).collect();
+        let large: Vec<u32> = (0..m).map(|_| rng.r#gen()).collect();
+
+        for sorter in sorters::<Counted<u32>>() {
+            let a = sort_stats(sorter.as_ref(), &small);
+            let b = sort_stats(sorter.as_ref(), &large);
+            let cost = |s: SortStats| (s.comparisons + s.writes) as f64;
+            // 4x the items: 16x for O(n²), a bit over 4x for O(n log n)
+            let growth = cost(b) / cost(a);
+            let quadratic = sorter.complexity().average == BigO::Quadratic;
+            assert_eq!(growth > 9.0, quadratic, "{} grew {growth}x", sorter.name());
+        }
+    }
+}
@@ -1,28 +1,45 @@
-fn merge(arr: &mut [i32], mid: usize) {
-    let left = arr[..mid].to_vec();
-    let right = arr[mid..].to_vec();
-    let mut l = 0;
-    let mut r = 0;
-
-    for val in arr {
-        if r == right.len() || (l < left.len() && left[l] < right[r]) {
-            *val = left[l];
-            l += 1;
-        } else {
-            *val = right[r];
-            r += 1;
-        }
-    }
+use std::cmp::Ordering;
+
+use crate::mergesort::merge2_by;
+
+/*
+* Merge sort (top down)
+* input: [5, 4, 1, 2, 3]
+* output: [1, 2, 3, 4, 5]
+*
+* sorts both halves, then merges them with merge2, which moves the left
+* half out to a buffer and merges back into the slice
+* O(n log n) comparisons, O(n / 2) extra space, stable
+* */
+pub fn merge_sort<T: Ord>(arr: &mut [T]) {
+    merge_sort_by(arr, T::cmp);
+}
+
+pub fn merge_sort_by<T, F>(arr: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort(arr, &mut cmp);
+}
+
+pub fn merge_sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_sort_by(arr, |a, b| f(a).cmp(&f(b)));
 }
 
-fn merge_sort(arr: &mut [i32]) -> Vec<i32> {
+fn sort<T, F>(arr: &mut [T], cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     if arr.len() > 1 {
         let mid = arr.len() / 2;
-        merge_sort(&mut arr[..mid]);
-        merge_sort(&mut arr[mid..]);
-        merge(arr, mid);
+        sort(&mut arr[..mid], cmp);
+        sort(&mut arr[mid..], cmp);
+        merge2_by(arr, mid, &mut *cmp);
     }
-    arr.to_vec()
 }
 
 #[cfg(test)]
@@ -32,6 +49,34 @@ mod tests {
     #[test]
     fn test_merge_sorting() {
         let mut arr = vec![5, 4, 1, 2, 3];
-        assert_eq!(merge_sort(&mut arr), vec![1, 2, 3, 4, 5]);
+        merge_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn sorts_records_that_are_not_clone_stably() {
+        #[derive(Debug, PartialEq)]
+        struct Order {
+            id: u32,
+            customer: String,
+        }
+        let order = |id, customer: &str| Order {
+            id,
+            customer: customer.to_string(),
+        };
+        let mut orders = vec![
+            order(4, "zoe"),
+            order(1, "al"),
+            order(3, "zoe"),
+            order(2, "al"),
+        ];
+
+        merge_sort_by(&mut orders, |a, b| a.customer.cmp(&b.customer));
+        let ids: Vec<u32> = orders.iter().map(|o| o.id).collect();
+        assert_eq!(ids, [1, 2, 4, 3]);
+
+        merge_sort_by_key(&mut orders, |o| o.id);
+        assert_eq!(orders[0], order(1, "al"));
+        assert_eq!(orders[3], order(4, "zoe"));
     }
 }
@@ -0,0 +1,20 @@
+//! Sorting algorithms. Each one sorts a `&mut [T]` in place and comes in
+//! three flavours: `x` for `T: Ord`, `x_by` with a comparator and `x_by_key`
+//! with a key extractor, mirroring `slice::sort`.
+
+pub mod bubble_sort;
+pub use bubble_sort::*;
+pub mod insert_sorting;
+pub use insert_sorting::*;
+pub mod selection_sorting;
+pub use selection_sorting::*;
+pub mod quicksort;
+pub use quicksort::*;
+pub mod merge_sort;
+pub use merge_sort::*;
+pub mod sorter;
+pub use sorter::*;
+pub mod instrument;
+pub use instrument::*;
+//pub mod recursion;
+//pub use recursion::*;
@@ -1,22 +1,71 @@
-fn quicksort(arr: &mut [i32], start: usize, end: usize) -> Vec<i32> {
-    if start < end {
-        let part = partition(arr, start, end);
-        quicksort(arr, start, part - 1);
-        quicksort(arr, part + 1, end);
+use std::cmp::Ordering;
+
+use super::instrument;
+
+/*
+* Quicksort (Lomuto partition, last item as pivot)
+* input: [5, 4, 1, 2, 3]
+* output: [1, 2, 3, 4, 5]
+*
+* partition puts the pivot in its final place with the smaller items
+* before it, then both sides are sorted on their own
+* O(n log n) on average, O(n²) on sorted input since the last item is
+* always the pivot, not stable
+* recursing into the smaller side and looping on the larger one keeps
+* the stack at O(log n) even in the worst case
+* */
+pub fn quicksort<T: Ord>(arr: &mut [T]) {
+    quicksort_by(arr, T::cmp);
+}
+
+pub fn quicksort_by<T, F>(arr: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort(arr, &mut cmp);
+}
+
+pub fn quicksort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    quicksort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+fn sort<T, F>(mut arr: &mut [T], cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while arr.len() > 1 {
+        let part = partition(arr, cmp);
+        let (left, right) = arr.split_at_mut(part);
+        let right = &mut right[1..];
+        if left.len() < right.len() {
+            sort(left, cmp);
+            arr = right;
+        } else {
+            sort(right, cmp);
+            arr = left;
+        }
     }
-    arr.to_vec()
 }
 
-fn partition(arr: &mut [i32], start: usize, end: usize) -> usize {
-    let mut i = start;
-    let pivot = end;
-    for j in start..=end - 1 {
-        if arr[j] < arr[pivot] {
+fn partition<T, F>(arr: &mut [T], cmp: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let pivot = arr.len() - 1;
+    let mut i = 0;
+    for j in 0..pivot {
+        if cmp(&arr[j], &arr[pivot]) == Ordering::Less {
             arr.swap(i, j);
+            instrument::swapped();
             i += 1;
         }
     }
     arr.swap(i, pivot);
+    instrument::swapped();
     i
 }
 
@@ -27,7 +76,28 @@ mod tests {
     #[test]
     fn test_bubble_sorting() {
         let mut arr = vec![5, 4, 1, 2, 3];
-        let len = arr.len();
-        assert_eq!(quicksort(&mut arr, 0, len - 1), vec![1, 2, 3, 4, 5]);
+        quicksort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn pivot_at_the_front_does_not_underflow() {
+        let mut arr = vec![1, 2];
+        quicksort(&mut arr);
+        assert_eq!(arr, [1, 2]);
+
+        let mut arr: Vec<u32> = (0..1000).collect();
+        quicksort(&mut arr);
+        assert!(arr.is_sorted());
+    }
+
+    #[test]
+    fn sorts_records_by_key() {
+        let mut files = vec![("b.rs", 300), ("a.rs", 10), ("c.rs", 20)];
+        quicksort_by_key(&mut files, |f| f.1);
+        assert_eq!(files, [("a.rs", 10), ("c.rs", 20), ("b.rs", 300)]);
+
+        quicksort_by(&mut files, |a, b| a.0.cmp(b.0));
+        assert_eq!(files, [("a.rs", 10), ("b.rs", 300), ("c.rs", 20)]);
     }
 }
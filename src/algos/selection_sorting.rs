@@ -1,34 +1,61 @@
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use std::cmp::Ordering;
+
+use super::instrument;
+
 /*
-* Selection selection_sort
-* input: mutable vector of i32 [5, 4, 1, 2, 3]
-* output: sorted vector of i32 [1, 2, 3, 4, 5]
+* Selection sort
+* input: [5, 4, 1, 2, 3]
+* output: [1, 2, 3, 4, 5]
+*
+* picks the smallest item of v[i..] and swaps it into v[i]
+* O(n²) comparisons but at most n - 1 swaps, not stable: the swap can
+* jump an item over an equal one
 * */
-fn selection_sort(vec: &mut Vec<i32>) -> Vec<i32> {
-    for i in 0..vec.len() - 1 {
+pub fn selection_sort<T: Ord>(vec: &mut [T]) {
+    selection_sort_by(vec, T::cmp);
+}
+
+pub fn selection_sort_by<T, F>(vec: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 0..vec.len().saturating_sub(1) {
         let mut smallest = i;
         for j in (i + 1)..vec.len() {
-            if vec[j] < vec[smallest] {
+            if cmp(&vec[j], &vec[smallest]) == Ordering::Less {
                 smallest = j;
             }
         }
-        vec.swap(smallest, i);
+        if smallest != i {
+            vec.swap(smallest, i);
+            instrument::swapped();
+        }
     }
-    vec.to_vec()
+}
+
+pub fn selection_sort_by_key<T, K, F>(vec: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    selection_sort_by(vec, |a, b| f(a).cmp(&f(b)));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::seq::SliceRandom;
+    use rand::thread_rng;
 
     #[test]
     fn test_selection_sort() {
         let mut arr = vec![5, 4, 1, 2, 3];
-        assert_eq!(selection_sort(&mut arr), vec![1, 2, 3, 4, 5]);
+        selection_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5]);
 
         let mut arr = vec![5, 4, 2, 3];
-        assert_eq!(selection_sort(&mut arr), vec![2, 3, 4, 5]);
+        selection_sort(&mut arr);
+        assert_eq!(arr, vec![2, 3, 4, 5]);
 
         let mut sorted_arr = Vec::new();
         let mut arr = Vec::new();
@@ -38,6 +65,29 @@ mod tests {
         }
 
         arr.shuffle(&mut thread_rng());
-        assert_eq!(selection_sort(&mut arr), sorted_arr);
+        selection_sort(&mut arr);
+        assert_eq!(arr, sorted_arr);
+    }
+
+    #[test]
+    fn items_already_in_place_are_not_swapped() {
+        let mut arr = vec![1, 2, 3, 4, 5];
+        let stats = instrument::measure(|| selection_sort(&mut arr)).1;
+        assert_eq!(stats.swaps, 0);
+
+        let mut arr = vec![2, 1, 3, 5, 4];
+        let stats = instrument::measure(|| selection_sort(&mut arr)).1;
+        assert_eq!(arr, [1, 2, 3, 4, 5]);
+        assert_eq!(stats.swaps, 2);
+    }
+
+    #[test]
+    fn sorts_records_by_key() {
+        let mut points: Vec<(i32, char)> = vec![(3, 'c'), (-1, 'a'), (2, 'b'), (-4, 'd')];
+        selection_sort_by_key(&mut points, |p| p.0.abs());
+        assert_eq!(points, [(-1, 'a'), (2, 'b'), (3, 'c'), (-4, 'd')]);
+
+        selection_sort_by(&mut points, |a, b| b.1.cmp(&a.1));
+        assert_eq!(points, [(-4, 'd'), (3, 'c'), (2, 'b'), (-1, 'a')]);
     }
 }
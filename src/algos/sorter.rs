@@ -0,0 +1,420 @@
+use std::fmt;
+
+use super::{bubble_sorting, insert_sort, instrument, merge_sort, quicksort, selection_sort};
+use crate::mergesort::{merge_inplace_insertion, merge3, mergesort, mergesort_vec_by};
+
+/*
+ * Sorter
+ * one trait over every sort in the algos module and mergesort.rs, so tests,
+ * benches and tools can pick them by name from `sorters()` instead of
+ * calling each function by hand.
+ *
+ *   sorter::<u32>("quicksort")
+ *        |
+ *        v
+ *   Box<dyn Sorter<u32>> --> sort(&mut [u32])
+ *                       \--> name / is_stable / is_in_place / complexity
+ *
+ * merge3 and merge_inplace_insertion only merge two runs, their sorters
+ * drive them from a top down merge sort.
+ *
+ * The tests below check every sorter against std's sort, and check the
+ * stability claims, so new sorts get both for free. The tests that stay
+ * next to each sort are its original example and a smoke test of its
+ * _by/_by_key variants, which the registry never calls since it only
+ * sorts by Ord. The merge functions are tested in mergesort.rs.
+ * */
+
+/// Growth class of a cost, ordered from cheapest to most expensive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BigO {
+    Constant,
+    Log,
+    Linear,
+    NLogN,
+    NLog2N,
+    Quadratic,
+}
+
+impl fmt::Display for BigO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BigO::Constant => "O(1)",
+            BigO::Log => "O(log n)",
+            BigO::Linear => "O(n)",
+            BigO::NLogN => "O(n log n)",
+            BigO::NLog2N => "O(n log² n)",
+            BigO::Quadratic => "O(n²)",
+        };
+        f.pad(s)
+    }
+}
+
+/// Declared time for the best, average and worst input, and the extra
+/// space on top of the slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complexity {
+    pub best: BigO,
+    pub average: BigO,
+    pub worst: BigO,
+    pub space: BigO,
+}
+
+pub trait Sorter<T: Ord> {
+    fn sort(&self, v: &mut [T]);
+
+    /// The name of the function behind it, which is also the registry key.
+    fn name(&self) -> &'static str;
+
+    /// Equal items keep their relative order.
+    fn is_stable(&self) -> bool;
+
+    /// Needs no more than O(log n) extra space.
+    fn is_in_place(&self) -> bool;
+
+    fn complexity(&self) -> Complexity;
+}
+
+impl<T: Ord> fmt::Debug for dyn Sorter<T> + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sorter")
+            .field("name", &self.name())
+            .field("stable", &self.is_stable())
+            .field("in_place", &self.is_in_place())
+            .finish()
+    }
+}
+
+macro_rules! sorter {
+    (
+        $(#[$meta:meta])*
+        $ty:ident,
+        $name:literal,
+        stable: $stable:literal,
+        in_place: $in_place:literal,
+        best: $best:ident,
+        average: $average:ident,
+        worst: $worst:ident,
+        space: $space:ident,
+        |$v:ident| $sort:expr
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $ty;
+
+        impl<T: Ord> Sorter<T> for $ty {
+            fn sort(&self, $v: &mut [T]) {
+                $sort
+            }
+
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            fn is_stable(&self) -> bool {
+                $stable
+            }
+
+            fn is_in_place(&self) -> bool {
+                $in_place
+            }
+
+            fn complexity(&self) -> Complexity {
+                Complexity {
+                    best: BigO::$best,
+                    average: BigO::$average,
+                    worst: BigO::$worst,
+                    space: BigO::$space,
+                }
+            }
+        }
+    };
+}
+
+sorter!(
+    /// [`bubble_sorting`], stops after a pass without swaps.
+    BubbleSort,
+    "bubble_sorting",
+    stable: true,
+    in_place: true,
+    best: Linear,
+    average: Quadratic,
+    worst: Quadratic,
+    space: Constant,
+    |v| bubble_sorting(v)
+);
+
+sorter!(
+    /// [`insert_sort`].
+    InsertionSort,
+    "insert_sort",
+    stable: true,
+    in_place: true,
+    best: Linear,
+    average: Quadratic,
+    worst: Quadratic,
+    space: Constant,
+    |v| insert_sort(v)
+);
+
+sorter!(
+    /// [`selection_sort`], quadratic comparisons on any input.
+    SelectionSort,
+    "selection_sort",
+    stable: false,
+    in_place: true,
+    best: Quadratic,
+    average: Quadratic,
+    worst: Quadratic,
+    space: Constant,
+    |v| selection_sort(v)
+);
+
+sorter!(
+    /// [`quicksort`], the last item is the pivot so sorted input is the
+    /// worst case.
+    QuickSort,
+    "quicksort",
+    stable: false,
+    in_place: true,
+    best: NLogN,
+    average: NLogN,
+    worst: Quadratic,
+    space: Log,
+    |v| quicksort(v)
+);
+
+sorter!(
+    /// [`merge_sort`], top down over `merge2`.
+    TopDownMergeSort,
+    "merge_sort",
+    stable: true,
+    in_place: false,
+    best: NLogN,
+    average: NLogN,
+    worst: NLogN,
+    space: Linear,
+    |v| merge_sort(v)
+);
+
+sorter!(
+    /// [`mergesort`], bottom up over `merge2`.
+    BottomUpMergeSort,
+    "mergesort",
+    stable: true,
+    in_place: false,
+    best: NLogN,
+    average: NLogN,
+    worst: NLogN,
+    space: Linear,
+    |v| mergesort(v)
+);
+
+sorter!(
+    /// [`mergesort_vec_by`] over the indices of the slice, the slice is
+    /// then put in that order with swaps, so items are never cloned.
+    VecMergeSort,
+    "mergesort_vec",
+    stable: true,
+    in_place: false,
+    best: NLogN,
+    average: NLogN,
+    worst: NLogN,
+    space: Linear,
+    |v| {
+        let order = mergesort_vec_by((0..v.len()).collect(), |&a, &b| v[a].cmp(&v[b]));
+        permute(v, order);
+    }
+);
+
+sorter!(
+    /// Top down merge sort over [`merge3`]. Only sorted runs skip the
+    /// rotations, anything else moves O(n²) items.
+    RotationMergeSort,
+    "merge3_sort",
+    stable: true,
+    in_place: true,
+    best: NLogN,
+    average: Quadratic,
+    worst: Quadratic,
+    space: Log,
+    |v| merge_sort_with(v, merge3)
+);
+
+sorter!(
+    /// Top down merge sort over [`merge_inplace_insertion`]. The binary
+    /// searches run even on sorted input, the rotations are what makes it
+    /// quadratic.
+    InsertionMergeSort,
+    "merge_inplace_insertion_sort",
+    stable: true,
+    in_place: true,
+    best: NLog2N,
+    average: Quadratic,
+    worst: Quadratic,
+    space: Log,
+    |v| merge_sort_with(v, merge_inplace_insertion)
+);
+
+fn merge_sort_with<T>(v: &mut [T], merge: fn(&mut [T], usize)) {
+    if v.len() > 1 {
+        let mid = v.len() / 2;
+        merge_sort_with(&mut v[..mid], merge);
+        merge_sort_with(&mut v[mid..], merge);
+        merge(v, mid);
+    }
+}
+
+// moves v[order[k]] to v[k] by walking each cycle of the permutation
+fn permute<T>(v: &mut [T], mut order: Vec<usize>) {
+    for start in 0..v.len() {
+        let mut k = start;
+        while order[k] != start {
+            let next = order[k];
+            v.swap(k, next);
+            instrument::swapped();
+            order[k] = k;
+            k = next;
+        }
+        order[k] = k;
+    }
+}
+
+/// Every sorter, the quadratic ones first.
+pub fn sorters<T: Ord>() -> Vec<Box<dyn Sorter<T>>> {
+    vec![
+        Box::new(BubbleSort),
+        Box::new(InsertionSort),
+        Box::new(SelectionSort),
+        Box::new(RotationMergeSort),
+        Box::new(InsertionMergeSort),
+        Box::new(QuickSort),
+        Box::new(TopDownMergeSort),
+        Box::new(BottomUpMergeSort),
+        Box::new(VecMergeSort),
+    ]
+}
+
+/// Looks a sorter up by [`Sorter::name`].
+pub fn sorter<T: Ord>(name: &str) -> Option<Box<dyn Sorter<T>>> {
+    sorters().into_iter().find(|s| s.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::cmp::Ordering;
+    use std::collections::HashSet;
+
+    // ordered by key only, seq tells equal keys apart
+    #[derive(Debug, Clone, Copy)]
+    struct Keyed {
+        key: u8,
+        seq: usize,
+    }
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    fn random_keys(rng: &mut StdRng, n: usize) -> Vec<Keyed> {
+        (0..n)
+            .map(|seq| Keyed {
+                key: rng.gen_range(0, 8),
+                seq,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_sorter_matches_std_sort() {
+        let mut rng = StdRng::seed_from_u64(49);
+        let sizes: &[usize] = if cfg!(miri) {
+            &[0, 1, 2, 7, 16]
+        } else {
+            &[0, 1, 2, 3, 10, 64, 257]
+        };
+        for sorter in sorters::<i32>() {
+            for &n in sizes {
+                let input: Vec<i32> = (0..n).map(|_| rng.gen_range(-50, 50)).collect();
+                let mut expected = input.clone();
+                expected.sort();
+                let mut got = input.clone();
+                sorter.sort(&mut got);
+                assert_eq!(got, expected, "{} on {input:?}", sorter.name());
+            }
+        }
+    }
+
+    #[test]
+    fn stability_claims_hold() {
+        let n = if cfg!(miri) { 40 } else { 200 };
+        for sorter in sorters::<Keyed>() {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut kept_order = true;
+            for _ in 0..5 {
+                let mut v = random_keys(&mut rng, n);
+                sorter.sort(&mut v);
+                assert!(v.is_sorted(), "{}", sorter.name());
+                kept_order &= v
+                    .windows(2)
+                    .all(|w| w[0].key != w[1].key || w[0].seq < w[1].seq);
+            }
+            assert_eq!(kept_order, sorter.is_stable(), "{}", sorter.name());
+        }
+    }
+
+    #[test]
+    fn sorters_move_items_that_are_not_clone() {
+        // a String per item, so Miri catches an item dropped twice
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Name(String);
+
+        let names = ["kim", "al", "zed", "bo", "al", "max"];
+        for sorter in sorters::<Name>() {
+            let mut v: Vec<Name> = names.map(|n| Name(n.to_string())).into();
+            sorter.sort(&mut v);
+            let got: Vec<&str> = v.iter().map(|n| n.0.as_str()).collect();
+            assert_eq!(
+                got,
+                ["al", "al", "bo", "kim", "max", "zed"],
+                "{}",
+                sorter.name()
+            );
+        }
+    }
+
+    #[test]
+    fn sorters_are_found_by_name() {
+        let names: Vec<&str> = sorters::<u8>().iter().map(|s| s.name()).collect();
+        let unique: HashSet<&str> = names.iter().copied().collect();
+        assert_eq!(unique.len(), names.len());
+
+        let quick = sorter::<u8>("quicksort").unwrap();
+        assert!(!quick.is_stable());
+        assert_eq!(quick.complexity().worst, BigO::Quadratic);
+        let mut v = vec![3, 1, 2];
+        quick.sort(&mut v);
+        assert_eq!(v, [1, 2, 3]);
+
+        assert!(sorter::<u8>("bogosort").is_none());
+        assert_eq!(format!("{:>12}", BigO::NLogN), "  O(n log n)");
+    }
+}
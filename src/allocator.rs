@@ -0,0 +1,391 @@
+use std::{
+    alloc::{self, Layout},
+    cell::{Cell, RefCell},
+    fmt,
+    ptr::{self, NonNull},
+};
+
+/*
+ * Allocator
+ * std::alloc::Allocator is still nightly only, so this is a small stable
+ * copy of it that MyVec can be generic over.
+ *
+ *   MyVec<T, A = Global>
+ *          |
+ *          v
+ *   allocate / grow / shrink / deallocate
+ *          |
+ *          +--> Global        -> std::alloc (the #[global_allocator])
+ *          +--> CountingAlloc -> records every call, then forwards to inner
+ * */
+
+/// The allocator could not satisfy the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "memory allocation failed")
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// # Safety
+///
+/// Blocks returned by `allocate`/`grow`/`shrink` must be valid for reads
+/// and writes of `layout.size()` bytes and stay valid until they are passed
+/// back to `deallocate`, `grow` or `shrink` on the same allocator (or a
+/// reference to it).
+pub unsafe trait Allocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Moves the block into one that fits `new_layout`, keeping the first
+    /// `old_layout.size()` bytes. On error the old block is left untouched.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this allocator with `old_layout`,
+    /// and `new_layout` must have the same align and a size that is not
+    /// smaller.
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let new = self.allocate(new_layout)?;
+        // SAFETY: both blocks hold at least old_layout.size() bytes and
+        // cannot overlap since the new one was just handed out
+        unsafe {
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+        }
+        Ok(new)
+    }
+
+    /// Moves the block into a smaller one that fits `new_layout`, keeping
+    /// the first `new_layout.size()` bytes. On error the old block is left
+    /// untouched.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this allocator with `old_layout`,
+    /// and `new_layout` must have the same align and a size that is not
+    /// bigger.
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let new = self.allocate(new_layout)?;
+        // SAFETY: both blocks hold at least new_layout.size() bytes and
+        // cannot overlap since the new one was just handed out
+        unsafe {
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr(), new_layout.size());
+            self.deallocate(ptr, old_layout);
+        }
+        Ok(new)
+    }
+}
+
+// SAFETY: forwards to A, which upholds the contract
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        unsafe { (**self).grow(ptr, old_layout, new_layout) }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        unsafe { (**self).shrink(ptr, old_layout, new_layout) }
+    }
+}
+
+/// The global allocator, the default for [`crate::MyVec`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Global;
+
+fn dangling(layout: Layout) -> NonNull<u8> {
+    // SAFETY: alignments are never zero
+    unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(layout.align())) }
+}
+
+// SAFETY: std::alloc hands out blocks that satisfy the contract
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        // std::alloc::alloc is UB for zero sized layouts
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        // SAFETY: layout has a non zero size
+        NonNull::new(unsafe { alloc::alloc(layout) }).ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert_eq!(old_layout.align(), new_layout.align());
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+        // SAFETY: ptr came from alloc::alloc with old_layout, and new size
+        // is not smaller so it is non zero too
+        let ptr = unsafe { alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) };
+        NonNull::new(ptr).ok_or(AllocError)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert_eq!(old_layout.align(), new_layout.align());
+        if new_layout.size() == 0 {
+            unsafe { self.deallocate(ptr, old_layout) };
+            return Ok(dangling(new_layout));
+        }
+        // SAFETY: ptr came from alloc::alloc with old_layout and the new
+        // size is non zero
+        let ptr = unsafe { alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) };
+        NonNull::new(ptr).ok_or(AllocError)
+    }
+}
+
+/// One call seen by [`CountingAlloc`], in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocEvent {
+    Alloc { size: usize },
+    Realloc { old_size: usize, new_size: usize },
+    Dealloc { size: usize },
+    Failed { size: usize },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocations: usize,
+    pub reallocations: usize,
+    pub deallocations: usize,
+    pub failures: usize,
+    pub bytes_in_use: usize,
+    pub peak_bytes_in_use: usize,
+    /// Bytes kept across every grow or shrink, which is what a realloc
+    /// has to copy in the worst case.
+    pub bytes_moved: usize,
+}
+
+/// Wraps another allocator and records every call made through it.
+/// Pass it by reference (`MyVec::new_in(&counter)`) to read the numbers
+/// back while the collection is still alive.
+#[derive(Debug, Default)]
+pub struct CountingAlloc<A = Global> {
+    inner: A,
+    stats: Cell<AllocStats>,
+    events: RefCell<Vec<AllocEvent>>,
+}
+
+impl CountingAlloc {
+    pub fn new() -> Self {
+        Self::with_inner(Global)
+    }
+}
+
+impl<A> CountingAlloc<A> {
+    pub fn with_inner(inner: A) -> Self {
+        CountingAlloc {
+            inner,
+            stats: Cell::new(AllocStats::default()),
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn stats(&self) -> AllocStats {
+        self.stats.get()
+    }
+
+    pub fn events(&self) -> Vec<AllocEvent> {
+        self.events.borrow().clone()
+    }
+
+    pub fn reset(&self) {
+        self.stats.set(AllocStats::default());
+        self.events.borrow_mut().clear();
+    }
+
+    fn record(&self, event: AllocEvent) {
+        let mut stats = self.stats.get();
+        match event {
+            AllocEvent::Alloc { size } => {
+                stats.allocations += 1;
+                stats.bytes_in_use += size;
+            }
+            AllocEvent::Realloc { old_size, new_size } => {
+                stats.reallocations += 1;
+                stats.bytes_moved += old_size.min(new_size);
+                stats.bytes_in_use = stats.bytes_in_use - old_size + new_size;
+            }
+            AllocEvent::Dealloc { size } => {
+                stats.deallocations += 1;
+                stats.bytes_in_use -= size;
+            }
+            AllocEvent::Failed { .. } => stats.failures += 1,
+        }
+        stats.peak_bytes_in_use = stats.peak_bytes_in_use.max(stats.bytes_in_use);
+        self.stats.set(stats);
+        self.events.borrow_mut().push(event);
+    }
+}
+
+// SAFETY: forwards to A, which upholds the contract
+unsafe impl<A: Allocator> Allocator for CountingAlloc<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let size = layout.size();
+        let res = self.inner.allocate(layout);
+        match res {
+            Ok(_) => self.record(AllocEvent::Alloc { size }),
+            Err(_) => self.record(AllocEvent::Failed { size }),
+        }
+        res
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.record(AllocEvent::Dealloc {
+            size: layout.size(),
+        });
+        unsafe { self.inner.deallocate(ptr, layout) }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let res = unsafe { self.inner.grow(ptr, old_layout, new_layout) };
+        match res {
+            Ok(_) => self.record(AllocEvent::Realloc {
+                old_size: old_layout.size(),
+                new_size: new_layout.size(),
+            }),
+            Err(_) => self.record(AllocEvent::Failed {
+                size: new_layout.size(),
+            }),
+        }
+        res
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let res = unsafe { self.inner.shrink(ptr, old_layout, new_layout) };
+        match res {
+            Ok(_) => self.record(AllocEvent::Realloc {
+                old_size: old_layout.size(),
+                new_size: new_layout.size(),
+            }),
+            Err(_) => self.record(AllocEvent::Failed {
+                size: new_layout.size(),
+            }),
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_alloc_tracks_usage() {
+        let counter = CountingAlloc::new();
+        let small = Layout::array::<u64>(4).unwrap();
+        let big = Layout::array::<u64>(8).unwrap();
+
+        let a = counter.allocate(small).unwrap();
+        let b = counter.allocate(small).unwrap();
+        let b = unsafe { counter.grow(b, small, big) }.unwrap();
+        unsafe { counter.deallocate(a, small) };
+        assert_eq!(counter.stats().bytes_in_use, 64);
+        unsafe { counter.deallocate(b, big) };
+
+        let stats = counter.stats();
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(stats.reallocations, 1);
+        assert_eq!(stats.deallocations, 2);
+        assert_eq!(stats.bytes_in_use, 0);
+        assert_eq!(stats.peak_bytes_in_use, 96);
+        assert_eq!(stats.bytes_moved, 32);
+        assert_eq!(
+            counter.events(),
+            vec![
+                AllocEvent::Alloc { size: 32 },
+                AllocEvent::Alloc { size: 32 },
+                AllocEvent::Realloc {
+                    old_size: 32,
+                    new_size: 64
+                },
+                AllocEvent::Dealloc { size: 32 },
+                AllocEvent::Dealloc { size: 64 },
+            ]
+        );
+    }
+
+    #[test]
+    fn default_grow_keeps_contents() {
+        struct NoRealloc;
+        // SAFETY: forwards to Global, only grow is left to the default
+        unsafe impl Allocator for NoRealloc {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+
+        let old = Layout::array::<u32>(2).unwrap();
+        let new = Layout::array::<u32>(4).unwrap();
+        let ptr = NoRealloc.allocate(old).unwrap();
+        unsafe {
+            ptr.cast::<[u32; 2]>().write([7, 9]);
+            let ptr = NoRealloc.grow(ptr, old, new).unwrap();
+            assert_eq!(ptr.cast::<[u32; 2]>().read(), [7, 9]);
+            NoRealloc.deallocate(ptr, new);
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+
+use crate::vector::MyVec;
+
+/*
+ * BitVec
+ * One bit per item packed into u64 words, so it takes 1/8 of the memory
+ * of MyVec<bool>. Bits past len in the last word are always kept at 0,
+ * which lets count_ones and the bitwise ops work a whole word at a time.
+ *
+ *   bit i lives in words[i / 64] at position i % 64
+ *
+ * Rank and select (RankSelect)
+ *   rank1(i)   = number of ones in [0, i)
+ *   select1(k) = position of the k-th one (0 based)
+ *
+ * Both are answered from two precomputed tables instead of scanning:
+ *   superblocks[s] = ones before superblock s   (512 bits, u64 counts)
+ *   blocks[w]      = ones before word w, counted from the start of its
+ *                    superblock (< 512, fits in a u16)
+ *
+ *   rank1(i)   = superblocks[i / 512] + blocks[i / 64] + popcount(word & mask)  O(1)
+ *   select1(k) = binary search on superblocks, then on blocks, then inside
+ *                the word                                                     O(log n)
+ *
+ * The tables cost 24 bits per 64 bit word (a u16 block count plus an
+ * eighth of a u64 superblock count). A RankSelect borrows the BitVec, so
+ * it can not get out of date.
+ * */
+
+const WORD_BITS: usize = 64;
+const SUPERBLOCK_WORDS: usize = 8;
+const SUPERBLOCK_BITS: usize = WORD_BITS * SUPERBLOCK_WORDS;
+
+#[derive(Default)]
+pub struct BitVec {
+    words: MyVec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        BitVec {
+            words: MyVec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        BitVec {
+            words: MyVec::with_capacity(bits.div_ceil(WORD_BITS)),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        let index = self.len;
+        self.len += 1;
+        self.set(index, bit);
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+        let word = self.words.as_slice()[index / WORD_BITS];
+        Some(word >> (index % WORD_BITS) & 1 == 1)
+    }
+
+    /// Returns the previous value, or None if the index is out of bounds.
+    pub fn set(&mut self, index: usize, bit: bool) -> Option<bool> {
+        let old = self.get(index)?;
+        let word = &mut self.words.as_mut_slice()[index / WORD_BITS];
+        let mask = 1 << (index % WORD_BITS);
+        if bit {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+        Some(old)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .as_slice()
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.get(i).unwrap_or_default())
+    }
+
+    /// Positions of the set bits, in increasing order.
+    pub fn iter_ones(&self) -> IterOnes<'_> {
+        IterOnes {
+            words: self.words.as_slice(),
+            word_index: 0,
+            current: self.words.as_slice().first().copied().unwrap_or(0),
+        }
+    }
+
+    pub fn words(&self) -> &[u64] {
+        self.words.as_slice()
+    }
+
+    /// Builds the rank/select tables for the current bits.
+    pub fn rank_select(&self) -> RankSelect<'_> {
+        RankSelect::new(self)
+    }
+
+    fn zip_words(&mut self, other: &BitVec, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(
+            self.len, other.len,
+            "bitwise ops need BitVecs of the same length"
+        );
+        for (a, b) in self
+            .words
+            .as_mut_slice()
+            .iter_mut()
+            .zip(other.words.as_slice())
+        {
+            *a = op(*a, *b);
+        }
+    }
+
+    fn copy(&self) -> BitVec {
+        let mut words = MyVec::with_capacity(self.words.len());
+        for w in self.words.as_slice() {
+            words.push(*w);
+        }
+        BitVec {
+            words,
+            len: self.len,
+        }
+    }
+}
+
+impl Clone for BitVec {
+    fn clone(&self) -> Self {
+        self.copy()
+    }
+}
+
+impl PartialEq for BitVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.words.as_slice() == other.words.as_slice()
+    }
+}
+
+impl Eq for BitVec {}
+
+impl std::fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for bit in self.iter() {
+            write!(f, "{}", bit as u8)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitVec::new();
+        for bit in iter {
+            bits.push(bit);
+        }
+        bits
+    }
+}
+
+// the ops keep the zero padding: and/or/xor of two zeros is zero
+macro_rules! bit_op {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident, $op:tt) => {
+        impl $assign_trait<&BitVec> for BitVec {
+            /// Panics if the lengths differ.
+            fn $assign_fn(&mut self, rhs: &BitVec) {
+                self.zip_words(rhs, |a, b| a $op b);
+            }
+        }
+
+        impl $trait<&BitVec> for &BitVec {
+            type Output = BitVec;
+            /// Panics if the lengths differ.
+            fn $fn(self, rhs: &BitVec) -> BitVec {
+                let mut out = self.copy();
+                out.zip_words(rhs, |a, b| a $op b);
+                out
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+pub struct IterOnes<'a> {
+    words: &'a [u64],
+    word_index: usize,
+    // bits of words[word_index] not yielded yet
+    current: u64,
+}
+
+impl Iterator for IterOnes<'_> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            self.word_index += 1;
+            self.current = *self.words.get(self.word_index)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // clear the lowest set bit
+        self.current &= self.current - 1;
+        Some(self.word_index * WORD_BITS + bit)
+    }
+}
+
+/// Position of the k-th (0 based) set bit of a word.
+fn select_in_word(mut word: u64, k: u32) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+pub struct RankSelect<'a> {
+    bits: &'a BitVec,
+    superblocks: Vec<u64>,
+    blocks: Vec<u16>,
+    ones: usize,
+}
+
+impl<'a> RankSelect<'a> {
+    pub fn new(bits: &'a BitVec) -> Self {
+        let words = bits.words.as_slice();
+        let mut superblocks = Vec::with_capacity(words.len().div_ceil(SUPERBLOCK_WORDS));
+        let mut blocks = Vec::with_capacity(words.len());
+        let mut total = 0u64;
+        let mut in_superblock = 0u16;
+        for (i, word) in words.iter().enumerate() {
+            if i.is_multiple_of(SUPERBLOCK_WORDS) {
+                superblocks.push(total);
+                in_superblock = 0;
+            }
+            blocks.push(in_superblock);
+            in_superblock += word.count_ones() as u16;
+            total += word.count_ones() as u64;
+        }
+        RankSelect {
+            bits,
+            superblocks,
+            blocks,
+            ones: total as usize,
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Number of set bits in `[0, index)`. Panics if index > len.
+    pub fn rank1(&self, index: usize) -> usize {
+        assert!(index <= self.bits.len, "rank1 index out of bounds");
+        if index == self.bits.len {
+            return self.ones;
+        }
+        let word = index / WORD_BITS;
+        let mask = (1u64 << (index % WORD_BITS)) - 1;
+        let in_word = (self.bits.words.as_slice()[word] & mask).count_ones() as usize;
+        self.superblocks[index / SUPERBLOCK_BITS] as usize + self.blocks[word] as usize + in_word
+    }
+
+    /// Number of unset bits in `[0, index)`. Panics if index > len.
+    pub fn rank0(&self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    /// Position of the k-th (0 based) set bit, None if there are not
+    /// that many.
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.ones {
+            return None;
+        }
+        let k = k as u64;
+        // last superblock that starts with at most k ones before it
+        let superblock = self.superblocks.partition_point(|&ones| ones <= k) - 1;
+        let k = (k - self.superblocks[superblock]) as u16;
+
+        let first = superblock * SUPERBLOCK_WORDS;
+        let last = (first + SUPERBLOCK_WORDS).min(self.blocks.len());
+        let word = first + self.blocks[first..last].partition_point(|&ones| ones <= k) - 1;
+        let k = (k - self.blocks[word]) as u32;
+
+        let bits = self.bits.words.as_slice()[word];
+        Some(word * WORD_BITS + select_in_word(bits, k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn push_get_set() {
+        let mut bits = BitVec::new();
+        assert!(bits.is_empty());
+        for i in 0..130 {
+            bits.push(i % 3 == 0);
+        }
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.words().len(), 3);
+        assert_eq!(bits.get(0), Some(true));
+        assert_eq!(bits.get(1), Some(false));
+        assert_eq!(bits.get(129), Some(true));
+        assert_eq!(bits.get(130), None);
+
+        assert_eq!(bits.set(1, true), Some(false));
+        assert_eq!(bits.get(1), Some(true));
+        assert_eq!(bits.set(0, false), Some(true));
+        assert_eq!(bits.get(0), Some(false));
+        assert_eq!(bits.set(130, true), None);
+    }
+
+    #[test]
+    fn counts() {
+        let bits: BitVec = (0..200).map(|i| i % 2 == 0).collect();
+        assert_eq!(bits.count_ones(), 100);
+        assert_eq!(bits.count_zeros(), 100);
+        assert_eq!(
+            format!("{:?}", BitVec::from_iter([true, false, true])),
+            "101"
+        );
+    }
+
+    #[test]
+    fn iter_ones() {
+        let bits: BitVec = (0..300)
+            .map(|i| i % 7 == 0 || i == 64 || i == 299)
+            .collect();
+        let expected: Vec<_> = (0..300)
+            .filter(|i| i % 7 == 0 || *i == 64 || *i == 299)
+            .collect();
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), expected);
+        assert_eq!(BitVec::new().iter_ones().next(), None);
+        let zeros: BitVec = (0..200).map(|_| false).collect();
+        assert_eq!(zeros.iter_ones().next(), None);
+    }
+
+    #[test]
+    fn bitwise_ops() {
+        let a: BitVec = [true, true, false, false].into_iter().collect();
+        let b: BitVec = [true, false, true, false].into_iter().collect();
+        assert_eq!(format!("{:?}", &a & &b), "1000");
+        assert_eq!(format!("{:?}", &a | &b), "1110");
+        assert_eq!(format!("{:?}", &a ^ &b), "0110");
+
+        let mut c = a.clone();
+        c ^= &b;
+        c |= &a;
+        c &= &b;
+        assert_eq!(format!("{:?}", c), "1010");
+    }
+
+    #[test]
+    #[should_panic]
+    fn bitwise_ops_need_same_len() {
+        let a: BitVec = [true].into_iter().collect();
+        let b: BitVec = [true, false].into_iter().collect();
+        let _ = &a & &b;
+    }
+
+    #[test]
+    fn rank_select_small() {
+        let bits: BitVec = [false, true, true, false, true].into_iter().collect();
+        let rs = bits.rank_select();
+        assert_eq!(rs.rank1(0), 0);
+        assert_eq!(rs.rank1(2), 1);
+        assert_eq!(rs.rank1(5), 3);
+        assert_eq!(rs.rank0(5), 2);
+        assert_eq!(rs.select1(0), Some(1));
+        assert_eq!(rs.select1(1), Some(2));
+        assert_eq!(rs.select1(2), Some(4));
+        assert_eq!(rs.select1(3), None);
+
+        let empty = BitVec::new();
+        let rs = empty.rank_select();
+        assert_eq!(rs.rank1(0), 0);
+        assert_eq!(rs.select1(0), None);
+    }
+
+    #[test]
+    fn rank_select_against_scan() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for density in [0.01, 0.5, 0.99] {
+            let bits: BitVec = (0..5000).map(|_| rng.gen_bool(density)).collect();
+            let rs = bits.rank_select();
+            let ones: Vec<_> = bits.iter_ones().collect();
+            assert_eq!(rs.count_ones(), ones.len());
+
+            let mut rank = 0;
+            for i in 0..bits.len() {
+                assert_eq!(rs.rank1(i), rank, "rank1({i})");
+                rank += bits.get(i).unwrap() as usize;
+            }
+            assert_eq!(rs.rank1(bits.len()), rank);
+            for (k, &pos) in ones.iter().enumerate() {
+                assert_eq!(rs.select1(k), Some(pos), "select1({k})");
+                assert_eq!(rs.rank1(pos), k);
+            }
+            assert_eq!(rs.select1(ones.len()), None);
+        }
+    }
+
+    #[test]
+    fn memory_is_one_bit_per_item() {
+        let bits: BitVec = (0..6400).map(|_| true).collect();
+        assert_eq!(bits.words().len() * 8, 6400 / 8);
+    }
+}
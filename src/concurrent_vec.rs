@@ -0,0 +1,333 @@
+use std::{
+    alloc::Layout,
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::allocator::{Allocator, Global};
+
+/*
+ * ConcurrentVec
+ * Append-only vector that many threads can push to through &self.
+ * Instead of one block that gets reallocated (and would invalidate every
+ * reference into it), items live in buckets that double in size and are
+ * never moved or freed until the vector is dropped.
+ *
+ *   bucket 0: [ 0 .. 32)     32 slots
+ *   bucket 1: [ 32 .. 96)    64 slots
+ *   bucket 2: [ 96 .. 224)   128 slots
+ *   ...
+ *
+ * push:
+ *   1. fetch_add on `reserved` hands out a unique index
+ *   2. the bucket for it is allocated on first use; threads race with a
+ *      compare_exchange and the losers free their copy
+ *   3. the item is written and its slot flagged ready (Release)
+ *
+ * get(i) only returns slots that are flagged ready (Acquire), so a reader
+ * never sees an index that was reserved but not written yet.
+ * No locks: push is a fetch_add plus, once per bucket, an allocation.
+ * */
+
+const FIRST_BUCKET_BITS: u32 = 5;
+const FIRST_BUCKET: usize = 1 << FIRST_BUCKET_BITS;
+const BUCKETS: usize = (usize::BITS - FIRST_BUCKET_BITS) as usize;
+
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+pub struct ConcurrentVec<T> {
+    buckets: [AtomicPtr<Slot<T>>; BUCKETS],
+    // indexes handed out so far, some may still be being written
+    reserved: AtomicUsize,
+}
+
+// SAFETY: items are only moved in by push and shared out as &T, so sending
+// the vector needs T: Send and sharing it needs T: Send + Sync
+unsafe impl<T: Send> Send for ConcurrentVec<T> {}
+unsafe impl<T: Send + Sync> Sync for ConcurrentVec<T> {}
+
+impl<T> Default for ConcurrentVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// (bucket, offset in bucket) for an index
+fn locate(index: usize) -> (usize, usize) {
+    let pos = index
+        .checked_add(FIRST_BUCKET)
+        .expect("ConcurrentVec index overflow");
+    let bit = usize::BITS - 1 - pos.leading_zeros();
+    let bucket = (bit - FIRST_BUCKET_BITS) as usize;
+    (bucket, pos - (1 << bit))
+}
+
+fn bucket_len(bucket: usize) -> usize {
+    FIRST_BUCKET << bucket
+}
+
+impl<T> ConcurrentVec<T> {
+    pub fn new() -> Self {
+        ConcurrentVec {
+            buckets: [const { AtomicPtr::new(ptr::null_mut()) }; BUCKETS],
+            reserved: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of pushes started so far. Some of the last items may still
+    /// be in flight on other threads, in which case `get` returns None for
+    /// them.
+    pub fn len(&self) -> usize {
+        self.reserved.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends the item and returns its index. Never moves existing items.
+    pub fn push(&self, item: T) -> usize {
+        let index = self.reserved.fetch_add(1, Ordering::AcqRel);
+        let (bucket, offset) = locate(index);
+        let slots = self.bucket_or_alloc(bucket);
+        // SAFETY: offset < bucket_len(bucket), and the fetch_add gave this
+        // index to us alone, so nobody else writes to the slot
+        unsafe {
+            let slot = &*slots.as_ptr().add(offset);
+            (*slot.value.get()).write(item);
+            slot.ready.store(true, Ordering::Release);
+        }
+        index
+    }
+
+    fn bucket_or_alloc(&self, bucket: usize) -> NonNull<Slot<T>> {
+        let current = self.buckets[bucket].load(Ordering::Acquire);
+        if let Some(slots) = NonNull::new(current) {
+            return slots;
+        }
+
+        let len = bucket_len(bucket);
+        let layout = Layout::array::<Slot<T>>(len).expect("ConcurrentVec bucket too big");
+        let new = Global
+            .allocate(layout)
+            .unwrap_or_else(|_| std::alloc::handle_alloc_error(layout))
+            .cast::<Slot<T>>();
+        for i in 0..len {
+            // SAFETY: the block has room for len slots
+            unsafe {
+                new.as_ptr().add(i).write(Slot {
+                    ready: AtomicBool::new(false),
+                    value: UnsafeCell::new(MaybeUninit::uninit()),
+                });
+            }
+        }
+
+        match self.buckets[bucket].compare_exchange(
+            ptr::null_mut(),
+            new.as_ptr(),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(winner) => {
+                // another thread installed its bucket first, ours was never
+                // shared so it can go straight back
+                unsafe { Global.deallocate(new.cast(), layout) };
+                // SAFETY: compare_exchange only fails on a non null value
+                unsafe { NonNull::new_unchecked(winner) }
+            }
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (bucket, offset) = locate(index);
+        let slots = self.buckets[bucket].load(Ordering::Acquire);
+        if slots.is_null() {
+            return None;
+        }
+        // SAFETY: offset is inside the bucket, and a ready slot was fully
+        // written before the Release store we synchronize with here. Items
+        // are never moved or dropped while &self is alive.
+        unsafe {
+            let slot = &*slots.add(offset);
+            if !slot.ready.load(Ordering::Acquire) {
+                return None;
+            }
+            Some((*slot.value.get()).assume_init_ref())
+        }
+    }
+
+    /// Items that were fully pushed when the iterator reached them, in
+    /// index order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+}
+
+impl<T> Drop for ConcurrentVec<T> {
+    fn drop(&mut self) {
+        for (bucket, ptr) in self.buckets.iter_mut().enumerate() {
+            let slots = *ptr.get_mut();
+            if slots.is_null() {
+                continue;
+            }
+            let len = bucket_len(bucket);
+            // SAFETY: &mut self means no push is in flight, so every slot
+            // is either ready and initialized or was never written
+            unsafe {
+                for i in 0..len {
+                    let slot = &mut *slots.add(i);
+                    if *slot.ready.get_mut() {
+                        slot.value.get_mut().assume_init_drop();
+                    }
+                }
+                let layout = Layout::array::<Slot<T>>(len).unwrap_unchecked();
+                Global.deallocate(NonNull::new_unchecked(slots).cast(), layout);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::HashSet,
+        sync::{Arc, Barrier},
+        thread,
+    };
+
+    #[test]
+    fn locate_buckets() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(31), (0, 31));
+        assert_eq!(locate(32), (1, 0));
+        assert_eq!(locate(95), (1, 63));
+        assert_eq!(locate(96), (2, 0));
+        assert_eq!(locate(usize::MAX - FIRST_BUCKET).0, BUCKETS - 1);
+    }
+
+    #[test]
+    fn push_and_get() {
+        let vec = ConcurrentVec::new();
+        assert!(vec.is_empty());
+        for i in 0..1000 {
+            assert_eq!(vec.push(i * 2), i);
+        }
+        assert_eq!(vec.len(), 1000);
+        assert_eq!(vec.get(0), Some(&0));
+        assert_eq!(vec.get(999), Some(&1998));
+        assert_eq!(vec.get(1000), None);
+        assert_eq!(vec.iter().count(), 1000);
+    }
+
+    #[test]
+    fn references_survive_pushes() {
+        let vec = ConcurrentVec::new();
+        vec.push(String::from("first"));
+        let first = vec.get(0).unwrap();
+        for i in 0..10_000 {
+            vec.push(i.to_string());
+        }
+        assert_eq!(first, "first");
+        assert_eq!(vec.get(10_000).map(String::as_str), Some("9999"));
+    }
+
+    #[test]
+    fn drops_items() {
+        let item = Arc::new(());
+        {
+            let vec = ConcurrentVec::new();
+            for _ in 0..100 {
+                vec.push(item.clone());
+            }
+            assert_eq!(Arc::strong_count(&item), 101);
+        }
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    // Stress harness: every thread waits on a barrier so they all hit the
+    // same (first) buckets at once, then pushes tagged items while reading
+    // back whatever is already there. Repeated to shake out interleavings.
+    fn stress(threads: usize, per_thread: usize) {
+        let vec = ConcurrentVec::new();
+        let barrier = Barrier::new(threads);
+        thread::scope(|s| {
+            for t in 0..threads {
+                let vec = &vec;
+                let barrier = &barrier;
+                s.spawn(move || {
+                    barrier.wait();
+                    let mut held = Vec::new();
+                    for i in 0..per_thread {
+                        let index = vec.push((t, i));
+                        // our own item is visible right after push
+                        let item = vec.get(index).expect("pushed item missing");
+                        assert_eq!(*item, (t, i));
+                        if i % 64 == 0 {
+                            held.push(item);
+                        }
+                        // reading others never yields a torn value
+                        if let Some(&(ot, oi)) = vec.get(index / 2) {
+                            assert!(ot < threads && oi < per_thread);
+                        }
+                    }
+                    // references taken earlier still point at our items
+                    for (n, item) in held.into_iter().enumerate() {
+                        assert_eq!(*item, (t, n * 64));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(vec.len(), threads * per_thread);
+        let seen: HashSet<_> = vec.iter().copied().collect();
+        assert_eq!(seen.len(), threads * per_thread, "lost or duplicated items");
+        for t in 0..threads {
+            for i in 0..per_thread {
+                assert!(seen.contains(&(t, i)));
+            }
+        }
+    }
+
+    #[test]
+    fn stress_many_small_rounds() {
+        for _ in 0..50 {
+            stress(8, 100);
+        }
+    }
+
+    #[test]
+    fn stress_large() {
+        stress(8, 20_000);
+    }
+
+    #[test]
+    fn shared_across_threads_with_arc() {
+        let vec = Arc::new(ConcurrentVec::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let vec = Arc::clone(&vec);
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        vec.push(t * 1000 + i);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let mut all: Vec<_> = vec.iter().copied().collect();
+        all.sort();
+        assert_eq!(all, (0..4000).collect::<Vec<_>>());
+    }
+}
@@ -3,7 +3,7 @@
 
  * Wrapper that uses arrays under the hood
  * push/pop/access has O(1)
- * enqueue/deque has O(N)
+ * enqueue/deque has O(N), RingBuffer does both ends in O(1)
  * constructor specifies initial size
 
 TypeScript example:
@@ -21,112 +21,218 @@ export default class ArrayList<T> {
 Array List
   - A dynamic array that can grow or shrink in size
   - It is a generic data structure that can store any type of data
-  - It is implemented using a Vec
+  - It is implemented on top of MyVec (uninitialized storage), so T
+    needs no Default or Clone
   - It has the following methods:
 */
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
-//#TODO : change it for rawvec
-type Array<T> = Vec<T>;
+use crate::{
+    allocator::Global,
+    growth::{Doubling, GrowthPolicy},
+    vector::{MyVec, TryReserveError},
+};
 
-#[derive(Debug)]
-pub struct ArrayList<T> {
+type Array<T, G> = MyVec<T, Global, G>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrayListError {
+    IndexOutOfBounds { index: usize, len: usize },
+    Alloc(TryReserveError),
+}
+
+impl fmt::Display for ArrayListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrayListError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} out of bounds for length {len}")
+            }
+            ArrayListError::Alloc(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ArrayListError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArrayListError::Alloc(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TryReserveError> for ArrayListError {
+    fn from(e: TryReserveError) -> Self {
+        ArrayListError::Alloc(e)
+    }
+}
+
+/// What an [`ArrayList`] observer is told about, right after it happened.
+#[derive(Debug, PartialEq)]
+pub enum ArrayListEvent<'a, T> {
+    Append(&'a T),
+    InsertAt(usize, &'a T),
+    RemoveAt(usize, &'a T),
+}
+
+type Observer<T> = Box<dyn FnMut(ArrayListEvent<'_, T>)>;
+
+/// Contract:
+/// - lookups that may find nothing (`get`, `pop`, `remove`) return `Option`
+/// - index based edits (`try_insert_at`, `remove_at`) return
+///   `Result<_, ArrayListError>` and leave the list unchanged on error
+/// - `try_append`/`try_insert_at` also report allocation failures, the
+///   plain `append`/`preprend`/`insert_at` abort on OOM like `Vec`, and
+///   `insert_at` panics on a bad index like `Vec::insert`
+pub struct ArrayList<T, G: GrowthPolicy = Doubling> {
+    /// Number of items, the same as `len()`. Every operation updates it,
+    /// writing to it changes nothing.
     pub length: usize,
-    inner: Array<T>,
-    capacity: usize,
+    inner: Array<T, G>,
+    observer: Option<Observer<T>>,
 }
 
-impl<T> ArrayList<T>
-where
-    T: Default + Clone + Debug + PartialEq,
-{
+impl<T> ArrayList<T> {
     pub fn new(capacity: usize) -> ArrayList<T> {
+        ArrayList::with_growth(capacity, Doubling)
+    }
+}
+
+impl<T, G: GrowthPolicy> ArrayList<T, G> {
+    pub fn with_growth(capacity: usize, growth: G) -> ArrayList<T, G> {
+        let mut inner = MyVec::with_growth(growth);
+        inner.reserve_exact(capacity);
         ArrayList {
             length: 0,
-            capacity,
-            inner: vec![T::default(); capacity],
+            inner,
+            observer: None,
         }
     }
 
-    fn shrink(&mut self) {
-        let prev = &self.inner;
-        let new_capacity = self.capacity * 2;
-        self.capacity = new_capacity;
-        let mut new = vec![T::default(); new_capacity];
-        for i in 0..prev.len() {
-            new[i] = prev[i].clone();
-        }
-        self.inner = new;
+    /// Calls `observer` after every append, prepend, insert_at and remove_at.
+    pub fn set_observer(&mut self, observer: impl FnMut(ArrayListEvent<'_, T>) + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.observer = None;
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.inner.as_slice()
     }
 
     pub fn append(&mut self, item: T) {
-        println!("Appending {item:?} to {self:?}");
-        if self.inner.len() == self.length {
-            self.shrink();
+        self.inner.push(item);
+        self.length = self.inner.len();
+        self.notify_append();
+    }
+
+    pub fn try_append(&mut self, item: T) -> Result<(), ArrayListError> {
+        self.inner.try_push(item)?;
+        self.length = self.inner.len();
+        self.notify_append();
+        Ok(())
+    }
+
+    fn notify_append(&mut self) {
+        if let Some(observer) = &mut self.observer {
+            let item = &self.inner.as_slice()[self.inner.len() - 1];
+            observer(ArrayListEvent::Append(item));
         }
-        self.inner[self.length] = item;
-        self.length += 1;
     }
 
-    pub fn pop(&mut self) -> T {
-        let item = self.inner[self.length - 1].clone();
-        self.inner[self.length - 1] = T::default();
-        self.length -= 1;
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.inner.pop();
+        self.length = self.inner.len();
         item
     }
+
     pub fn get(&self, idx: usize) -> Option<&T> {
-        match idx < self.length {
-            true => Some(&self.inner[idx]),
-            _ => None,
-        }
+        self.inner.get(idx)
     }
+
+    /// # Panics
+    ///
+    /// If `idx > len`, see [`ArrayList::try_insert_at`].
     pub fn insert_at(&mut self, item: T, idx: usize) {
-        if self.inner.len() == self.length {
-            self.shrink();
+        if let Err(e) = self.try_insert_at(item, idx) {
+            panic!("{e}");
+        }
+    }
+
+    /// Inserts at `idx`, where `idx == len` appends.
+    pub fn try_insert_at(&mut self, item: T, idx: usize) -> Result<(), ArrayListError> {
+        let len = self.len();
+        if idx > len {
+            return Err(ArrayListError::IndexOutOfBounds { index: idx, len });
         }
-        for i in ((idx + 1)..=self.length).rev() {
-            self.inner[i] = self.inner[i - 1].clone();
+        self.inner.try_reserve(1)?;
+        self.inner.insert(idx, item);
+        self.length = self.inner.len();
+        if let Some(observer) = &mut self.observer {
+            observer(ArrayListEvent::InsertAt(idx, &self.inner.as_slice()[idx]));
         }
-        self.inner[idx] = item;
-        self.length += 1;
+        Ok(())
     }
 
+    /// Reported to the observer as `InsertAt(0, item)`.
     pub fn preprend(&mut self, item: T) {
-        println!("Prepending {item:?} to {self:?}");
         self.insert_at(item, 0);
     }
 
-    pub fn remove(&mut self, item: &T) -> Option<T> {
-        for i in 0..self.length {
-            if &self.inner[i] == item {
-                return self.remove_at(i).unwrap()
-            }
-        }
-        None
-
+    pub fn remove(&mut self, item: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let idx = self.inner.as_slice().iter().position(|x| x == item)?;
+        self.remove_at(idx).ok()
     }
 
-    pub fn remove_at(&mut self, idx: usize) -> Result<Option<T>, &str> {
-        println!("Removing at {idx} from {self:?}");
-        match idx < self.length {
-            true => {
-                let item = self.inner[idx].clone();
-                for i in idx..(self.length-1) {
-                    self.inner[i] = self.inner[i + 1].clone();
-                }
-                self.inner[self.length - 1] = T::default();
-                self.length -= 1;
-                Ok(Some(item))
-            }
-            _ => Err("Index out of bounds"),
+    pub fn remove_at(&mut self, idx: usize) -> Result<T, ArrayListError> {
+        let len = self.len();
+        let item = self
+            .inner
+            .remove(idx)
+            .ok_or(ArrayListError::IndexOutOfBounds { index: idx, len })?;
+        self.length = self.inner.len();
+        if let Some(observer) = &mut self.observer {
+            observer(ArrayListEvent::RemoveAt(idx, &item));
         }
+        Ok(item)
+    }
+}
+
+impl<T: Debug, G: GrowthPolicy> Debug for ArrayList<T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayList")
+            .field("length", &self.len())
+            .field("capacity", &self.capacity())
+            .field("items", &self.as_slice())
+            .finish()
     }
 }
 
+#[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::growth::{FixedIncrement, ShrinkOnUnderflow};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::{cell::RefCell, fmt::Display, fs::File, rc::Rc};
 
     #[test]
     fn test_array_list_append() {
@@ -146,22 +252,22 @@ mod tests {
     fn test_array_list_append_shrink() {
         let mut arr = ArrayList::new(4);
         arr.append(1);
-        assert_eq!(arr.inner[0], 1);
-        assert_eq!(arr.inner[1], 0);
-        assert_eq!(arr.inner[2], 0);
-        assert_eq!(arr.inner[3], 0);
+        assert_eq!(arr.get(0), Some(&1));
+        assert_eq!(arr.get(1), None);
+        assert_eq!(arr.get(2), None);
+        assert_eq!(arr.get(3), None);
         arr.append(2);
-        assert_eq!(arr.inner[0], 1);
-        assert_eq!(arr.inner[1], 2);
-        assert_eq!(arr.inner[2], 0);
-        assert_eq!(arr.inner[3], 0);
+        assert_eq!(arr.get(0), Some(&1));
+        assert_eq!(arr.get(1), Some(&2));
+        assert_eq!(arr.get(2), None);
+        assert_eq!(arr.get(3), None);
         arr.append(3);
         arr.append(4);
         arr.append(4);
-        assert_eq!(arr.inner[4], 4);
-        assert_eq!(arr.inner[5], 0);
+        assert_eq!(arr.get(4), Some(&4));
+        assert_eq!(arr.get(5), None);
         assert_eq!(arr.length, 5);
-        assert_eq!(arr.capacity, 8);
+        assert_eq!(arr.capacity(), 8);
     }
 
     #[test]
@@ -172,16 +278,16 @@ mod tests {
         arr.append(3);
         arr.append(-1);
         assert_eq!(4, arr.length);
-        assert_eq!(-1, arr.pop());
-        assert_eq!(4, arr.capacity);
+        assert_eq!(Some(-1), arr.pop());
+        assert_eq!(4, arr.capacity());
         arr.append(1);
         arr.append(2);
         arr.append(3);
-        assert_eq!(8, arr.capacity);
-        assert_eq!(3, arr.pop());
-        assert_eq!(2, arr.pop());
-        assert_eq!(1, arr.pop());
-        assert_eq!(vec![1, 2, 3, 0, 0, 0, 0, 0], arr.inner);
+        assert_eq!(8, arr.capacity());
+        assert_eq!(Some(3), arr.pop());
+        assert_eq!(Some(2), arr.pop());
+        assert_eq!(Some(1), arr.pop());
+        assert_eq!(&[1, 2, 3], arr.as_slice());
     }
 
     #[test]
@@ -217,11 +323,205 @@ mod tests {
         arr.append(4);
         arr.append(5);
         let i = arr.remove_at(1).unwrap();
-        assert_eq!(Some(2), i);
-        assert_eq!(vec![1,4,5,0], arr.inner);
+        assert_eq!(2, i);
+        assert_eq!(&[1, 4, 5], arr.as_slice());
         let _ = arr.remove_at(0).unwrap();
-        assert_eq!(vec![4,5,0,0], arr.inner);
+        assert_eq!(&[4, 5], arr.as_slice());
         let i = arr.remove(&5).unwrap();
         assert_eq!(5, i);
     }
+
+    #[test]
+    fn test_growth_policy() {
+        let mut arr = ArrayList::with_growth(2, FixedIncrement(3));
+        for i in 0..5 {
+            arr.append(i);
+        }
+        assert_eq!(5, arr.capacity());
+        arr.append(5);
+        assert_eq!(8, arr.capacity());
+
+        let mut arr = ArrayList::with_growth(0, ShrinkOnUnderflow(Doubling));
+        for i in 0..16 {
+            arr.append(i);
+        }
+        assert_eq!(16, arr.capacity());
+        for _ in 0..12 {
+            arr.pop();
+        }
+        assert_eq!(8, arr.capacity());
+        assert_eq!(Ok(0), arr.remove_at(0));
+        assert_eq!(8, arr.capacity());
+        assert_eq!(Some(3), arr.pop());
+        assert_eq!(4, arr.capacity());
+        assert_eq!(&[1, 2], arr.as_slice());
+    }
+
+    #[test]
+    fn test_non_clone_items() {
+        let mut arr: ArrayList<Box<dyn Display>> = ArrayList::new(0);
+        arr.append(Box::new(1));
+        arr.append(Box::new("two"));
+        arr.preprend(Box::new(0.5));
+        let shown: Vec<_> = arr.as_slice().iter().map(|d| d.to_string()).collect();
+        assert_eq!(vec!["0.5", "1", "two"], shown);
+        let removed = arr.remove_at(1).unwrap();
+        assert_eq!("1", removed.to_string());
+
+        let mut files = ArrayList::new(1);
+        files.append(File::open("Cargo.toml").unwrap());
+        files.append(File::open("src/lib.rs").unwrap());
+        assert_eq!(2, files.len());
+        assert!(files.pop().unwrap().metadata().unwrap().is_file());
+    }
+
+    #[test]
+    fn test_drops_items() {
+        let item = Rc::new(());
+        let mut arr = ArrayList::new(2);
+        for _ in 0..5 {
+            arr.append(item.clone());
+        }
+        arr.remove_at(2).unwrap();
+        assert_eq!(5, Rc::strong_count(&item));
+        drop(arr);
+        assert_eq!(1, Rc::strong_count(&item));
+    }
+
+    #[test]
+    fn test_observer() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut arr = ArrayList::new(4);
+        let sink = log.clone();
+        arr.set_observer(move |event| sink.borrow_mut().push(format!("{event:?}")));
+        arr.append(1);
+        arr.append(2);
+        arr.preprend(0);
+        arr.insert_at(7, 2);
+        assert!(arr.try_insert_at(8, 9).is_err());
+        arr.remove_at(1).unwrap();
+        assert!(arr.remove_at(9).is_err());
+        arr.clear_observer();
+        arr.append(3);
+        assert_eq!(
+            vec![
+                "Append(1)",
+                "Append(2)",
+                "InsertAt(0, 0)",
+                "InsertAt(2, 7)",
+                "RemoveAt(1, 1)"
+            ],
+            *log.borrow()
+        );
+        assert_eq!(&[0, 7, 2, 3], arr.as_slice());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut arr = ArrayList::new(4);
+        arr.append(1);
+        arr.append(2);
+        assert_eq!(
+            "ArrayList { length: 2, capacity: 4, items: [1, 2] }",
+            format!("{arr:?}")
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut arr = ArrayList::new(2);
+        assert_eq!(None, arr.pop());
+        assert_eq!(
+            Err(ArrayListError::IndexOutOfBounds { index: 0, len: 0 }),
+            arr.remove_at(0)
+        );
+        assert_eq!(
+            Err(ArrayListError::IndexOutOfBounds { index: 1, len: 0 }),
+            arr.try_insert_at(1, 1)
+        );
+        assert_eq!(Ok(()), arr.try_insert_at(1, 0));
+        assert_eq!(Ok(()), arr.try_append(2));
+        assert_eq!(None, arr.remove(&7));
+        assert_eq!(&[1, 2], arr.as_slice());
+        assert_eq!(
+            "index 5 out of bounds for length 2",
+            arr.remove_at(5).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for length 1")]
+    fn test_insert_at_panics() {
+        let mut arr = ArrayList::new(1);
+        arr.append(1);
+        arr.insert_at(2, 3);
+    }
+
+    // runs random operations against a Vec and checks both agree after
+    // every step, including the out of range ones
+    fn check_against_vec<G: GrowthPolicy>(growth: G, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut arr = ArrayList::with_growth(0, growth);
+        let mut model: Vec<u32> = Vec::new();
+        for _ in 0..2000 {
+            let value = rng.gen_range(0, 50);
+            let idx = rng.gen_range(0, model.len() + 3);
+            match rng.gen_range(0, 8) {
+                0 => {
+                    arr.append(value);
+                    model.push(value);
+                }
+                1 => {
+                    arr.preprend(value);
+                    model.insert(0, value);
+                }
+                2 => {
+                    let idx = idx.min(model.len());
+                    arr.insert_at(value, idx);
+                    model.insert(idx, value);
+                }
+                3 => {
+                    let res = arr.try_insert_at(value, idx);
+                    if idx <= model.len() {
+                        assert_eq!(Ok(()), res);
+                        model.insert(idx, value);
+                    } else {
+                        assert!(res.is_err());
+                    }
+                }
+                4 => {
+                    let expected = if idx < model.len() {
+                        Ok(model.remove(idx))
+                    } else {
+                        Err(ArrayListError::IndexOutOfBounds {
+                            index: idx,
+                            len: model.len(),
+                        })
+                    };
+                    assert_eq!(expected, arr.remove_at(idx));
+                }
+                5 => {
+                    let expected = model
+                        .iter()
+                        .position(|x| *x == value)
+                        .map(|i| model.remove(i));
+                    assert_eq!(expected, arr.remove(&value));
+                }
+                6 => assert_eq!(model.pop(), arr.pop()),
+                _ => assert_eq!(model.get(idx), arr.get(idx)),
+            }
+            assert_eq!(model.as_slice(), arr.as_slice());
+            assert_eq!(model.len(), arr.length);
+            assert!(arr.capacity() >= arr.len());
+        }
+    }
+
+    #[test]
+    fn test_matches_vec() {
+        for seed in 0..20 {
+            check_against_vec(Doubling, seed);
+            check_against_vec(ShrinkOnUnderflow(Doubling), seed);
+            check_against_vec(FixedIncrement(3), seed);
+        }
+    }
 }
@@ -0,0 +1,594 @@
+use std::{collections::HashMap, hash::Hash, mem};
+
+use super::dlist::{DList, NodeHandle};
+
+/*
+ * LruCache / LfuCache
+ * A HashMap from key to a NodeHandle into a DList, so a lookup finds its
+ * node in O(1) and can unlink or move it without walking the list.
+ *
+ * LruCache keeps one list from most to least recently used. A hit moves
+ * the node to the front, eviction takes the back.
+ *
+ *   map { a, b, c }     front -> [ c ] <-> [ a ] <-> [ b ] <- back (evict)
+ *
+ * LfuCache keeps a list of frequency buckets in ascending order, and in
+ * every bucket a list of the entries used that often, most recent first.
+ * The map holds a handle to the bucket and one to the entry inside it.
+ * A hit moves the entry to the bucket for one use more (creating it right
+ * after the current one if needed, dropping the old one if it empties).
+ * Eviction takes the back of the first bucket: the least frequently used
+ * entry, the least recently used one among ties.
+ *
+ *   buckets   [ 1 ] <-------> [ 2 ] <-------> [ 5 ]
+ *              |               |               |
+ *             [ d ] <-> [ e ] [ a ]           [ b ] <-> [ c ]
+ *                        ^ evicted next
+ *
+ * get/put/remove/evict are O(1) for both.
+ *
+ * Capacity is a budget of weight. `new` weighs every entry as 1, so the
+ * capacity is an entry count; `with_weigher` takes a function of the key
+ * and value instead (bytes, cost, ...). An entry heavier than the whole
+ * capacity is not stored. Every entry the cache drops to stay in budget
+ * is handed to the on_evict callback; `remove`, `evict` and replaced
+ * values are returned to the caller instead.
+ * */
+
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize>;
+type OnEvict<K, V> = Box<dyn FnMut(K, V)>;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    weight: usize,
+}
+
+pub struct LruCache<K, V> {
+    // every handle is a node of `order`, removed from the map when its
+    // node is unlinked
+    map: HashMap<K, NodeHandle<Entry<K, V>>>,
+    // most recently used at the front
+    order: DList<Entry<K, V>>,
+    weight: usize,
+    capacity: usize,
+    weigher: Weigher<K, V>,
+    on_evict: Option<OnEvict<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Holds up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Holds entries up to a total weight of `capacity`.
+    pub fn with_weigher(capacity: usize, weigher: impl Fn(&K, &V) -> usize + 'static) -> Self {
+        LruCache {
+            map: HashMap::new(),
+            order: DList::new(),
+            weight: 0,
+            capacity,
+            weigher: Box::new(weigher),
+            on_evict: None,
+        }
+    }
+
+    /// Calls `on_evict` with every entry dropped to stay within capacity.
+    pub fn set_on_evict(&mut self, on_evict: impl FnMut(K, V) + 'static) {
+        self.on_evict = Some(Box::new(on_evict));
+    }
+
+    pub fn clear_on_evict(&mut self) {
+        self.on_evict = None;
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Total weight of the entries, `len` when counting entries.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Looks up without counting as a use.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let node = *self.map.get(key)?;
+        // SAFETY: handles in the map are nodes of order
+        Some(unsafe { &self.order.node(node).value })
+    }
+
+    /// Looks up and marks the entry most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = *self.map.get(key)?;
+        // SAFETY: handles in the map are nodes of order
+        unsafe {
+            self.order.move_to_front(node);
+            Some(&mut self.order.node_mut(node).value)
+        }
+    }
+
+    /// Inserts or replaces `key` as the most recently used entry and
+    /// returns the replaced value. Evicts least recently used entries
+    /// until the new one fits.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        let weight = (self.weigher)(&key, &value);
+        let old = self.remove(&key);
+        if weight > self.capacity {
+            self.notify_evict(key, value);
+            return old;
+        }
+        while self.weight + weight > self.capacity {
+            let (key, value) = self.evict().expect("weight is left, so entries are");
+            self.notify_evict(key, value);
+        }
+        let node = self.order.push_front_node(Entry {
+            key: key.clone(),
+            value,
+            weight,
+        });
+        self.map.insert(key, node);
+        self.weight += weight;
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.map.remove(key)?;
+        // SAFETY: handles in the map are nodes of order
+        let entry = unsafe { self.order.remove_node(node) };
+        self.weight -= entry.weight;
+        Some(entry.value)
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn evict(&mut self) -> Option<(K, V)> {
+        let entry = self.order.pop_back()?;
+        self.map.remove(&entry.key);
+        self.weight -= entry.weight;
+        Some((entry.key, entry.value))
+    }
+
+    fn notify_evict(&mut self, key: K, value: V) {
+        if let Some(on_evict) = &mut self.on_evict {
+            on_evict(key, value);
+        }
+    }
+
+    /// From most to least recently used.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.order.iter().map(|entry| (&entry.key, &entry.value))
+    }
+}
+
+struct Bucket<K, V> {
+    uses: u64,
+    // entries used `uses` times, most recent first
+    entries: DList<Entry<K, V>>,
+}
+
+impl<K, V> Bucket<K, V> {
+    fn new(uses: u64) -> Self {
+        Bucket {
+            uses,
+            entries: DList::new(),
+        }
+    }
+}
+
+// Where an LfuCache entry lives: `entry` is a node of the entries of
+// `bucket`, which is a node of the bucket list.
+struct Slot<K, V> {
+    bucket: NodeHandle<Bucket<K, V>>,
+    entry: NodeHandle<Entry<K, V>>,
+}
+
+impl<K, V> Clone for Slot<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Slot<K, V> {}
+
+pub struct LfuCache<K, V> {
+    // every slot points at live nodes, removed from the map when its
+    // entry is unlinked
+    map: HashMap<K, Slot<K, V>>,
+    // ascending by uses, never holds an empty bucket
+    buckets: DList<Bucket<K, V>>,
+    weight: usize,
+    capacity: usize,
+    weigher: Weigher<K, V>,
+    on_evict: Option<OnEvict<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    /// Holds up to `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Holds entries up to a total weight of `capacity`.
+    pub fn with_weigher(capacity: usize, weigher: impl Fn(&K, &V) -> usize + 'static) -> Self {
+        LfuCache {
+            map: HashMap::new(),
+            buckets: DList::new(),
+            weight: 0,
+            capacity,
+            weigher: Box::new(weigher),
+            on_evict: None,
+        }
+    }
+
+    /// Calls `on_evict` with every entry dropped to stay within capacity.
+    pub fn set_on_evict(&mut self, on_evict: impl FnMut(K, V) + 'static) {
+        self.on_evict = Some(Box::new(on_evict));
+    }
+
+    pub fn clear_on_evict(&mut self) {
+        self.on_evict = None;
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Total weight of the entries, `len` when counting entries.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// How many times `key` was put or looked up since it was inserted.
+    pub fn uses(&self, key: &K) -> Option<u64> {
+        let slot = *self.map.get(key)?;
+        // SAFETY: slots in the map are live
+        Some(unsafe { self.buckets.node(slot.bucket).uses })
+    }
+
+    /// Looks up without counting as a use.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let slot = *self.map.get(key)?;
+        // SAFETY: slots in the map are live
+        Some(unsafe { &entry(&self.buckets, slot).value })
+    }
+
+    /// Looks up and counts a use.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let slot = self.map.get_mut(key)?;
+        // SAFETY: slots in the map are live, touch keeps it that way
+        unsafe {
+            *slot = touch(&mut self.buckets, *slot);
+            let slot = *slot;
+            Some(&mut entry_mut(&mut self.buckets, slot).value)
+        }
+    }
+
+    /// Inserts `key` with one use, or replaces its value and counts a use,
+    /// and returns the replaced value. A new entry is made room for by
+    /// evicting first, so it is never its own victim; a replaced one
+    /// stays in the running and goes if it is still the least used.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        let weight = (self.weigher)(&key, &value);
+        if weight > self.capacity {
+            let old = self.remove(&key);
+            self.notify_evict(key, value);
+            return old;
+        }
+        if let Some(slot) = self.map.get_mut(&key) {
+            // SAFETY: slots in the map are live, touch keeps it that way
+            let entry = unsafe {
+                *slot = touch(&mut self.buckets, *slot);
+                let slot = *slot;
+                entry_mut(&mut self.buckets, slot)
+            };
+            let old = mem::replace(&mut entry.value, value);
+            self.weight = self.weight - entry.weight + weight;
+            entry.weight = weight;
+            self.evict_to_capacity(0);
+            return Some(old);
+        }
+        self.evict_to_capacity(weight);
+        let bucket = match self.buckets.front_node() {
+            // SAFETY: the front node is a node of buckets
+            Some(first) if unsafe { self.buckets.node(first).uses } == 1 => first,
+            _ => self.buckets.push_front_node(Bucket::new(1)),
+        };
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            weight,
+        };
+        // SAFETY: bucket was just found or pushed
+        let entry = unsafe { self.buckets.node_mut(bucket).entries.push_front_node(entry) };
+        self.map.insert(key, Slot { bucket, entry });
+        self.weight += weight;
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.map.remove(key)?;
+        // SAFETY: the slot was in the map, so it is live
+        let entry = unsafe { unlink_entry(&mut self.buckets, slot) };
+        self.weight -= entry.weight;
+        Some(entry.value)
+    }
+
+    /// Removes and returns the least frequently used entry, the least
+    /// recently used one among ties.
+    pub fn evict(&mut self) -> Option<(K, V)> {
+        let first = self.buckets.front_mut()?;
+        let entry = first.entries.pop_back().expect("empty buckets are dropped");
+        if first.entries.is_empty() {
+            self.buckets.pop_front();
+        }
+        self.map.remove(&entry.key);
+        self.weight -= entry.weight;
+        Some((entry.key, entry.value))
+    }
+
+    fn evict_to_capacity(&mut self, incoming: usize) {
+        while self.weight + incoming > self.capacity {
+            let (key, value) = self.evict().expect("weight is left, so entries are");
+            self.notify_evict(key, value);
+        }
+    }
+
+    fn notify_evict(&mut self, key: K, value: V) {
+        if let Some(on_evict) = &mut self.on_evict {
+            on_evict(key, value);
+        }
+    }
+}
+
+// SAFETY: slot must be live in `buckets`.
+unsafe fn entry<K, V>(buckets: &DList<Bucket<K, V>>, slot: Slot<K, V>) -> &Entry<K, V> {
+    unsafe { buckets.node(slot.bucket).entries.node(slot.entry) }
+}
+
+// SAFETY: slot must be live in `buckets`.
+unsafe fn entry_mut<K, V>(buckets: &mut DList<Bucket<K, V>>, slot: Slot<K, V>) -> &mut Entry<K, V> {
+    unsafe { buckets.node_mut(slot.bucket).entries.node_mut(slot.entry) }
+}
+
+// Moves the entry one bucket up and returns where it went.
+//
+// SAFETY: slot must be live in `buckets`, it is not afterwards.
+unsafe fn touch<K, V>(buckets: &mut DList<Bucket<K, V>>, slot: Slot<K, V>) -> Slot<K, V> {
+    unsafe {
+        let uses = buckets.node(slot.bucket).uses + 1;
+        let bucket = match buckets.next_node(slot.bucket) {
+            Some(next) if buckets.node(next).uses == uses => next,
+            _ => buckets.insert_after_node(slot.bucket, Bucket::new(uses)),
+        };
+        let entry = unlink_entry(buckets, slot);
+        let entry = buckets.node_mut(bucket).entries.push_front_node(entry);
+        Slot { bucket, entry }
+    }
+}
+
+// Unlinks the entry from its bucket and drops the bucket if it empties.
+//
+// SAFETY: slot must be live in `buckets`, it is not afterwards.
+unsafe fn unlink_entry<K, V>(buckets: &mut DList<Bucket<K, V>>, slot: Slot<K, V>) -> Entry<K, V> {
+    unsafe {
+        let entries = &mut buckets.node_mut(slot.bucket).entries;
+        let entry = entries.remove_node(slot.entry);
+        if entries.is_empty() {
+            buckets.remove_node(slot.bucket);
+        }
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::{cell::RefCell, rc::Rc};
+
+    type Evicted<K, V> = Rc<RefCell<Vec<(K, V)>>>;
+
+    fn recorder<K: 'static, V: 'static>() -> (Evicted<K, V>, impl FnMut(K, V)) {
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let sink = evicted.clone();
+        (evicted, move |k, v| sink.borrow_mut().push((k, v)))
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        let (evicted, on_evict) = recorder();
+        cache.set_on_evict(on_evict);
+        assert_eq!(cache.put("a", 1), None);
+        cache.put("b", 2);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        cache.put("c", 3);
+        assert_eq!(*evicted.borrow(), [("b", 2)]);
+        assert!(!cache.contains(&"b"));
+        assert_eq!(cache.iter().collect::<Vec<_>>(), [(&"c", &3), (&"a", &1)]);
+
+        // peek does not count as a use
+        assert_eq!(cache.peek(&"a"), Some(&1));
+        assert_eq!(cache.put("c", 30), Some(3));
+        cache.put("d", 4);
+        assert_eq!(*evicted.borrow(), [("b", 2), ("a", 1)]);
+        assert_eq!(cache.len(), 2);
+
+        *cache.get_mut(&"c").unwrap() += 1;
+        assert_eq!(cache.evict(), Some(("d", 4)));
+        assert_eq!(cache.remove(&"c"), Some(31));
+        assert_eq!(cache.remove(&"c"), None);
+        assert!(cache.is_empty());
+        assert_eq!(cache.evict(), None);
+        assert_eq!(evicted.borrow().len(), 2);
+    }
+
+    #[test]
+    fn lru_by_weight() {
+        let mut cache = LruCache::with_weigher(10, |_: &u32, v: &String| v.len());
+        let (evicted, on_evict) = recorder();
+        cache.set_on_evict(on_evict);
+        cache.put(1, "aaaa".to_string());
+        cache.put(2, "bbbb".to_string());
+        assert_eq!(cache.weight(), 8);
+        cache.put(3, "cccccc".to_string());
+        assert_eq!(cache.weight(), 10);
+        assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), [3, 2]);
+        // too heavy for the whole cache: not stored, the rest stays
+        cache.put(4, "x".repeat(11));
+        assert_eq!(cache.len(), 2);
+        // replacing reweighs
+        cache.put(2, "b".to_string());
+        assert_eq!(cache.weight(), 7);
+        let keys: Vec<_> = evicted.borrow().iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [1, 4]);
+        cache.clear_on_evict();
+        cache.put(5, "eeeeeeeeee".to_string());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(evicted.borrow().len(), 2);
+    }
+
+    #[test]
+    fn lfu_evicts_least_frequently_used() {
+        let mut cache = LfuCache::new(3);
+        let (evicted, on_evict) = recorder();
+        cache.set_on_evict(on_evict);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"b");
+        assert_eq!(cache.uses(&"a"), Some(3));
+        assert_eq!(cache.uses(&"c"), Some(1));
+        cache.put("d", 4);
+        assert_eq!(*evicted.borrow(), [("c", 3)]);
+        // d (1 use) goes before b (2 uses)
+        cache.put("e", 5);
+        assert_eq!(*evicted.borrow(), [("c", 3), ("d", 4)]);
+        // ties go to the least recently used: b and e both have 2 now
+        cache.get(&"e");
+        assert_eq!(cache.evict(), Some(("b", 2)));
+        assert_eq!(cache.put("a", 10), Some(1));
+        assert_eq!(cache.uses(&"a"), Some(4));
+        assert_eq!(cache.peek(&"a"), Some(&10));
+        assert_eq!(cache.uses(&"a"), Some(4));
+        assert_eq!(cache.remove(&"e"), Some(5));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.evict(), Some(("a", 10)));
+        assert_eq!(cache.evict(), None);
+        assert!(cache.buckets.is_empty());
+    }
+
+    #[test]
+    fn lfu_by_weight() {
+        let mut cache = LfuCache::with_weigher(10, |_: &u32, v: &Vec<u8>| v.len());
+        let (evicted, on_evict) = recorder();
+        cache.set_on_evict(on_evict);
+        cache.put(1, vec![0; 4]);
+        cache.put(2, vec![0; 4]);
+        cache.get(&1);
+        cache.put(3, vec![0; 5]);
+        assert_eq!(cache.weight(), 9);
+        assert!(!cache.contains(&2));
+        // growing a replaced value evicts the least used other entry
+        cache.get(&1);
+        cache.put(1, vec![0; 6]);
+        assert!(!cache.contains(&3));
+        assert_eq!(cache.weight(), 6);
+        cache.put(4, vec![0; 20]);
+        let keys: Vec<_> = evicted.borrow().iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [2, 3, 4]);
+        assert_eq!(cache.len(), 1);
+    }
+
+    // naive models: a Vec of (key, value, last use, uses) scanned linearly
+    #[test]
+    fn matches_naive_models() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut lru = LruCache::new(8);
+        let mut lfu = LfuCache::new(8);
+        let mut lru_model: Vec<(u8, u32, usize)> = Vec::new();
+        let mut lfu_model: Vec<(u8, u32, usize, u64)> = Vec::new();
+        for step in 0..3000 {
+            let key = rng.gen_range(0, 16);
+            if rng.gen_range(0, 2) == 0 {
+                let expected = lru_model.iter_mut().find(|e| e.0 == key).map(|e| {
+                    e.2 = step;
+                    e.1
+                });
+                assert_eq!(lru.get(&key).copied(), expected);
+                let expected = lfu_model.iter_mut().find(|e| e.0 == key).map(|e| {
+                    e.2 = step;
+                    e.3 += 1;
+                    e.1
+                });
+                assert_eq!(lfu.get(&key).copied(), expected);
+            } else {
+                let value = step as u32;
+                match lru_model.iter_mut().find(|e| e.0 == key) {
+                    Some(e) => *e = (key, value, step),
+                    None => {
+                        if lru_model.len() == 8 {
+                            let oldest = (0..8).min_by_key(|i| lru_model[*i].2).unwrap();
+                            lru_model.remove(oldest);
+                        }
+                        lru_model.push((key, value, step));
+                    }
+                }
+                lru.put(key, value);
+                match lfu_model.iter_mut().find(|e| e.0 == key) {
+                    Some(e) => *e = (key, value, step, e.3 + 1),
+                    None => {
+                        if lfu_model.len() == 8 {
+                            let victim = (0..8)
+                                .min_by_key(|i| (lfu_model[*i].3, lfu_model[*i].2))
+                                .unwrap();
+                            lfu_model.remove(victim);
+                        }
+                        lfu_model.push((key, value, step, 1));
+                    }
+                }
+                lfu.put(key, value);
+            }
+            assert_eq!(lru.len(), lru_model.len());
+            assert_eq!(lfu.len(), lfu_model.len());
+            for e in &lfu_model {
+                assert_eq!(lfu.uses(&e.0), Some(e.3));
+            }
+        }
+    }
+}
@@ -0,0 +1,1289 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug},
+    marker::PhantomData,
+    mem,
+    ptr::NonNull,
+};
+
+/*
+ * DList
+ * Doubly linked list over raw NonNull pointers, the same layout as
+ * std::collections::LinkedList. Unlike the Rc<RefCell> LinkedList there
+ * is no reference counting or runtime borrow checking, and nodes can be
+ * edited in the middle through a cursor.
+ *
+ *           head                      tail
+ *            |                         |
+ *            v                         v
+ *   None <- [ a ] <-> [ b ] <-> [ c ] -> None
+ *                       ^
+ *                 CursorMut (index 1)
+ *
+ * A CursorMut sits on one element, or on the "ghost" position between
+ * the tail and the head (moving next from the tail or prev from the head
+ * lands there, and moving again wraps around).
+ *
+ * O(1): push/pop at both ends, and through the cursor move_next,
+ * move_prev, insert_before, insert_after, remove_current, splice_after
+ * (the whole other list is linked in) and split_after (the tail is cut
+ * off; the cursor tracks its index so both lengths are known).
+ *
+ * A NodeHandle (from push_front_node / push_back_node) points straight at
+ * one node, so it can be read, removed or moved to either end in O(1)
+ * without walking the list, which is what LruCache and LfuCache keep in
+ * their maps.
+ *
+ * On the whole list: append is O(1), reverse, split_at, dedup and retain
+ * are O(n), sort is a bottom-up merge sort in O(n log n) and merge_sorted
+ * is O(n + m). Nodes are relinked in place, never reallocated.
+ * */
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+    prev: Link<T>,
+}
+
+pub struct DList<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    // the list owns its nodes, as if they were boxes
+    _boxed: PhantomData<Box<Node<T>>>,
+}
+
+// SAFETY: a DList owns its items like a Vec, the raw pointers are never
+// shared outside of borrows of the list
+unsafe impl<T: Send> Send for DList<T> {}
+unsafe impl<T: Sync> Sync for DList<T> {}
+
+impl<T> Default for DList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DList<T> {
+    pub fn new() -> Self {
+        DList {
+            head: None,
+            tail: None,
+            len: 0,
+            _boxed: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Links a new node right after `prev`, or at the front for None.
+    //
+    // SAFETY: prev must be None or a node of this list.
+    unsafe fn link_after(&mut self, prev: Link<T>, elem: T) -> NonNull<Node<T>> {
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            next: None,
+            prev: None,
+        })));
+        unsafe { self.link_node_after(prev, node) };
+        self.len += 1;
+        node
+    }
+
+    // SAFETY: node must be a node of this list, it is freed here.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        unsafe {
+            self.unlink_node(node);
+            self.len -= 1;
+            Box::from_raw(node.as_ptr()).elem
+        }
+    }
+
+    // Puts `node` in the chain right after `prev`, or at the front for
+    // None. Leaves len alone.
+    //
+    // SAFETY: prev must be None or a node of this list, node must not be
+    // linked anywhere.
+    unsafe fn link_node_after(&mut self, prev: Link<T>, node: NonNull<Node<T>>) {
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).next,
+                None => self.head,
+            };
+            (*node.as_ptr()).prev = prev;
+            (*node.as_ptr()).next = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+    }
+
+    // Takes `node` out of the chain without freeing it. Leaves len alone
+    // and the node's own links stale.
+    //
+    // SAFETY: node must be a node of this list.
+    unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) {
+        unsafe {
+            let (prev, next) = ((*node.as_ptr()).prev, (*node.as_ptr()).next);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = prev,
+                None => self.tail = prev,
+            }
+        }
+    }
+
+    // Moves every node of `other` in right after `prev` (front for None).
+    //
+    // SAFETY: prev must be None or a node of this list.
+    unsafe fn splice_after_node(&mut self, prev: Link<T>, mut other: DList<T>) {
+        let (Some(first), Some(last)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        let len = mem::take(&mut other.len);
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).next,
+                None => self.head,
+            };
+            (*first.as_ptr()).prev = prev;
+            (*last.as_ptr()).next = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(first),
+                None => self.head = Some(first),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(last),
+                None => self.tail = Some(last),
+            }
+        }
+        self.len += len;
+    }
+
+    // Cuts off everything after `node`, which sits at `index`.
+    //
+    // SAFETY: node must be a node of this list at that index.
+    unsafe fn split_after_node(&mut self, node: NonNull<Node<T>>, index: usize) -> DList<T> {
+        unsafe {
+            let Some(next) = (*node.as_ptr()).next.take() else {
+                return DList::new();
+            };
+            (*next.as_ptr()).prev = None;
+            let rest = DList {
+                head: Some(next),
+                tail: self.tail,
+                len: self.len - index - 1,
+                _boxed: PhantomData,
+            };
+            self.tail = Some(node);
+            self.len = index + 1;
+            rest
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        // SAFETY: None means the front
+        unsafe { self.link_after(None, elem) };
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        // SAFETY: the tail belongs to this list
+        unsafe { self.link_after(self.tail, elem) };
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        // SAFETY: the head belongs to this list
+        self.head.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        // SAFETY: the tail belongs to this list
+        self.tail.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: nodes live as long as the list, &self keeps them shared
+        self.head.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as above, &mut self makes the access unique
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: nodes live as long as the list, &self keeps them shared
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as above, &mut self makes the access unique
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> DListIter<'_, T> {
+        DListIter {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> DListIterMut<'_, T> {
+        DListIterMut {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn reverse(&mut self) {
+        let mut current = self.head;
+        while let Some(node) = current {
+            // SAFETY: every node reached from the head belongs to this list
+            unsafe {
+                let node = &mut *node.as_ptr();
+                mem::swap(&mut node.next, &mut node.prev);
+                current = node.prev;
+            }
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    /// Keeps the first `at` items and returns the rest.
+    ///
+    /// # Panics
+    ///
+    /// If `at > len`.
+    pub fn split_at(&mut self, at: usize) -> DList<T> {
+        if at > self.len {
+            panic!("index {at} out of bounds for length {}", self.len);
+        }
+        if at == 0 {
+            return mem::take(self);
+        }
+        let mut cursor = self.cursor_front_mut();
+        while cursor.index() != Some(at - 1) {
+            cursor.move_next();
+        }
+        cursor.split_after()
+    }
+
+    /// Moves every item of `other` to the end, leaving it empty.
+    pub fn append(&mut self, other: &mut DList<T>) {
+        // SAFETY: the tail belongs to this list
+        unsafe { self.splice_after_node(self.tail, mem::take(other)) };
+    }
+
+    /// Stable, equal items keep their order.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut cmp: F) {
+        let len = self.len;
+        let mut head = self.detach();
+        // merge neighbouring runs of width 1, 2, 4, ... until one is left
+        let mut width = 1;
+        while width < len {
+            let mut rest = head.take();
+            let mut last: Link<T> = None;
+            while let Some(left) = rest {
+                // SAFETY: the detached nodes are only reachable from here
+                unsafe {
+                    let right = cut(left, width);
+                    rest = right.and_then(|right| cut(right, width));
+                    let (first, end) = merge(Some(left), right, &mut cmp);
+                    match last {
+                        Some(last) => (*last.as_ptr()).next = first,
+                        None => head = first,
+                    }
+                    last = end;
+                }
+            }
+            width *= 2;
+        }
+        // SAFETY: head is the sorted chain of all len detached nodes
+        unsafe { self.attach(head, len) };
+    }
+
+    /// Merges two sorted lists into one sorted list. On ties items of
+    /// `self` come first.
+    pub fn merge_sorted(&mut self, mut other: DList<T>)
+    where
+        T: Ord,
+    {
+        let len = self.len + other.len;
+        let (ours, theirs) = (self.detach(), other.detach());
+        // SAFETY: both chains were just detached, the merge holds all nodes
+        unsafe {
+            let (head, _) = merge(ours, theirs, &mut T::cmp);
+            self.attach(head, len);
+        }
+    }
+
+    /// Removes consecutive repeated items, like [`Vec::dedup`].
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        let mut current = self.head;
+        while let Some(node) = current {
+            // SAFETY: node and its prev belong to this list, node is only
+            // freed after the last read
+            unsafe {
+                current = (*node.as_ptr()).next;
+                if let Some(prev) = (*node.as_ptr()).prev
+                    && (*prev.as_ptr()).elem == (*node.as_ptr()).elem
+                {
+                    drop(self.unlink(node));
+                }
+            }
+        }
+    }
+
+    /// Keeps only the items `f` returns true for, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut current = self.head;
+        while let Some(node) = current {
+            // SAFETY: node belongs to this list, next is read before it is freed
+            unsafe {
+                current = (*node.as_ptr()).next;
+                if !f(&(*node.as_ptr()).elem) {
+                    drop(self.unlink(node));
+                }
+            }
+        }
+    }
+
+    // Takes the nodes out as a chain linked by next only. Until attach
+    // puts them back the list is empty, so a panicking comparison leaks
+    // the nodes instead of leaving a half linked list to drop.
+    fn detach(&mut self) -> Link<T> {
+        self.tail = None;
+        self.len = 0;
+        self.head.take()
+    }
+
+    // Makes the chain starting at `head` the list again, fixing up the
+    // prev links and the tail.
+    //
+    // SAFETY: head must be a chain of `len` detached nodes, ending in None.
+    unsafe fn attach(&mut self, head: Link<T>, len: usize) {
+        let mut prev = None;
+        let mut current = head;
+        while let Some(node) = current {
+            unsafe {
+                (*node.as_ptr()).prev = prev;
+                current = (*node.as_ptr()).next;
+            }
+            prev = Some(node);
+        }
+        self.head = head;
+        self.tail = prev;
+        self.len = len;
+    }
+
+    /// Cursor on the first element, or on the ghost if the list is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Cursor on the last element, or on the ghost if the list is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: self.len.saturating_sub(1),
+            list: self,
+        }
+    }
+
+    /// Pushes like push_front and returns a handle to the new node.
+    pub fn push_front_node(&mut self, elem: T) -> NodeHandle<T> {
+        // SAFETY: None means the front
+        NodeHandle(unsafe { self.link_after(None, elem) })
+    }
+
+    /// Pushes like push_back and returns a handle to the new node.
+    pub fn push_back_node(&mut self, elem: T) -> NodeHandle<T> {
+        // SAFETY: the tail belongs to this list
+        NodeHandle(unsafe { self.link_after(self.tail, elem) })
+    }
+
+    pub fn front_node(&self) -> Option<NodeHandle<T>> {
+        self.head.map(NodeHandle)
+    }
+
+    pub fn back_node(&self) -> Option<NodeHandle<T>> {
+        self.tail.map(NodeHandle)
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn insert_after_node(&mut self, node: NodeHandle<T>, elem: T) -> NodeHandle<T> {
+        NodeHandle(unsafe { self.link_after(Some(node.0), elem) })
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn next_node(&self, node: NodeHandle<T>) -> Option<NodeHandle<T>> {
+        unsafe { (*node.0.as_ptr()).next.map(NodeHandle) }
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn prev_node(&self, node: NodeHandle<T>) -> Option<NodeHandle<T>> {
+        unsafe { (*node.0.as_ptr()).prev.map(NodeHandle) }
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn node(&self, node: NodeHandle<T>) -> &T {
+        unsafe { &(*node.0.as_ptr()).elem }
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn node_mut(&mut self, node: NodeHandle<T>) -> &mut T {
+        unsafe { &mut (*node.0.as_ptr()).elem }
+    }
+
+    /// Unlinks the node in O(1) and returns its element. The handle is
+    /// dangling afterwards.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn remove_node(&mut self, node: NodeHandle<T>) -> T {
+        unsafe { self.unlink(node.0) }
+    }
+
+    /// Relinks the node at the front in O(1), the handle stays valid.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn move_to_front(&mut self, node: NodeHandle<T>) {
+        unsafe {
+            self.unlink_node(node.0);
+            self.link_node_after(None, node.0);
+        }
+    }
+
+    /// Relinks the node at the back in O(1), the handle stays valid.
+    ///
+    /// # Safety
+    ///
+    /// `node` must be a node of this list (see [`NodeHandle`]).
+    pub unsafe fn move_to_back(&mut self, node: NodeHandle<T>) {
+        unsafe {
+            self.unlink_node(node.0);
+            self.link_node_after(self.tail, node.0);
+        }
+    }
+}
+
+/// Points at one node of a [`DList`], for O(1) access, removal and
+/// moves without a cursor, the way an LRU cache keeps a map from key to
+/// node. A handle is just a pointer: it stays valid while its node is
+/// linked in the list it came from (moving the list itself is fine), and
+/// dangles once the node is removed, popped, cleared or the list is
+/// dropped. Appending, splitting, sorting or merging can move the node to
+/// another list, after which the handle belongs to that one. The methods
+/// taking a handle are unsafe because the list cannot check any of this.
+pub struct NodeHandle<T>(NonNull<Node<T>>);
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeHandle<T> {}
+
+impl<T> PartialEq for NodeHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for NodeHandle<T> {}
+
+impl<T> Debug for NodeHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NodeHandle").field(&self.0).finish()
+    }
+}
+
+// Cuts a chain after `n` nodes and returns what came after.
+//
+// SAFETY: node must start a chain of valid nodes linked by next.
+unsafe fn cut<T>(node: NonNull<Node<T>>, n: usize) -> Link<T> {
+    let mut last = node;
+    unsafe {
+        for _ in 1..n {
+            match (*last.as_ptr()).next {
+                Some(next) => last = next,
+                None => return None,
+            }
+        }
+        (*last.as_ptr()).next.take()
+    }
+}
+
+// Stable merge of two sorted chains linked by next, ties are taken from
+// `a`. Returns the first and last node, prev links are left stale.
+//
+// SAFETY: a and b must be separate chains of valid nodes ending in None.
+unsafe fn merge<T, F>(mut a: Link<T>, mut b: Link<T>, cmp: &mut F) -> (Link<T>, Link<T>)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut head = None;
+    let mut last: Link<T> = None;
+    unsafe {
+        loop {
+            let node = match (a, b) {
+                (Some(x), Some(y))
+                    if cmp(&(*y.as_ptr()).elem, &(*x.as_ptr()).elem) == Ordering::Less =>
+                {
+                    b = (*y.as_ptr()).next;
+                    y
+                }
+                (Some(x), Some(_)) => {
+                    a = (*x.as_ptr()).next;
+                    x
+                }
+                _ => break,
+            };
+            match last {
+                Some(last) => (*last.as_ptr()).next = Some(node),
+                None => head = Some(node),
+            }
+            last = Some(node);
+        }
+        let rest = a.or(b);
+        match last {
+            Some(last) => (*last.as_ptr()).next = rest,
+            None => head = rest,
+        }
+        // the leftover run is already linked, walk to its end
+        last = last.or(rest);
+        while let Some(next) = last.and_then(|last| (*last.as_ptr()).next) {
+            last = Some(next);
+        }
+    }
+    (head, last)
+}
+
+impl<T> Drop for DList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Cursor that can edit a [`DList`] in the middle in O(1).
+pub struct CursorMut<'a, T> {
+    // None is the ghost position between tail and head
+    current: Link<T>,
+    // index of current, len when on the ghost
+    index: usize,
+    list: &'a mut DList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    /// Index of the current element, None on the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: the node belongs to the list we borrow mutably
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match self.current {
+            // SAFETY: the node belongs to the list we borrow mutably
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+        // SAFETY: as above
+        next.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match self.current {
+            // SAFETY: the node belongs to the list we borrow mutably
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        // SAFETY: as above
+        prev.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn move_next(&mut self) {
+        match self.current {
+            Some(node) => {
+                // SAFETY: the node belongs to the list
+                self.current = unsafe { (*node.as_ptr()).next };
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head;
+                self.index = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.current {
+            Some(node) => {
+                // SAFETY: the node belongs to the list
+                self.current = unsafe { (*node.as_ptr()).prev };
+                self.index = match self.current {
+                    Some(_) => self.index - 1,
+                    None => self.list.len,
+                };
+            }
+            None => {
+                self.current = self.list.tail;
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Inserts after the current element, at the front on the ghost.
+    pub fn insert_after(&mut self, elem: T) {
+        // SAFETY: current is None or a node of the list
+        unsafe { self.list.link_after(self.current, elem) };
+        if self.current.is_none() {
+            self.index += 1;
+        }
+    }
+
+    /// Inserts before the current element, at the back on the ghost.
+    pub fn insert_before(&mut self, elem: T) {
+        let prev = match self.current {
+            // SAFETY: the node belongs to the list
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+        // SAFETY: prev is None or a node of the list
+        unsafe { self.list.link_after(prev, elem) };
+        self.index += 1;
+    }
+
+    /// Removes the current element and moves to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        // SAFETY: the node belongs to the list, and we stop pointing at it
+        // before it is freed
+        unsafe {
+            self.current = (*node.as_ptr()).next;
+            Some(self.list.unlink(node))
+        }
+    }
+
+    /// Links all of `other` in after the current element (at the front on
+    /// the ghost). The cursor stays where it is.
+    pub fn splice_after(&mut self, other: DList<T>) {
+        let len = other.len;
+        // SAFETY: current is None or a node of the list
+        unsafe { self.list.splice_after_node(self.current, other) };
+        if self.current.is_none() {
+            self.index += len;
+        }
+    }
+
+    /// Cuts off and returns everything after the current element. On the
+    /// ghost that is the whole list.
+    pub fn split_after(&mut self) -> DList<T> {
+        match self.current {
+            // SAFETY: the node belongs to the list and sits at self.index
+            Some(node) => unsafe { self.list.split_after_node(node, self.index) },
+            None => {
+                self.index = 0;
+                mem::take(self.list)
+            }
+        }
+    }
+}
+
+pub struct DListIter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    _list: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for DListIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.remaining -= 1;
+            // SAFETY: the list is borrowed for 'a, so the node is alive
+            let node = unsafe { &*node.as_ptr() };
+            self.front = node.next;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for DListIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.remaining -= 1;
+            // SAFETY: the list is borrowed for 'a, so the node is alive
+            let node = unsafe { &*node.as_ptr() };
+            self.back = node.prev;
+            &node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for DListIter<'_, T> {}
+
+pub struct DListIterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    _list: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for DListIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.remaining -= 1;
+            // SAFETY: the list is borrowed mutably for 'a and every node
+            // is handed out once, so the references never alias
+            let node = unsafe { &mut *node.as_ptr() };
+            self.front = node.next;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for DListIterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.remaining -= 1;
+            // SAFETY: as in next
+            let node = unsafe { &mut *node.as_ptr() };
+            self.back = node.prev;
+            &mut node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for DListIterMut<'_, T> {}
+
+pub struct DListIntoIter<T>(DList<T>);
+
+impl<T> Iterator for DListIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for DListIntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for DListIntoIter<T> {}
+
+impl<T> IntoIterator for DList<T> {
+    type Item = T;
+    type IntoIter = DListIntoIter<T>;
+    fn into_iter(self) -> DListIntoIter<T> {
+        DListIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DList<T> {
+    type Item = &'a T;
+    type IntoIter = DListIter<'a, T>;
+    fn into_iter(self) -> DListIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for DList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DList::new();
+        for elem in iter {
+            list.push_back(elem);
+        }
+        list
+    }
+}
+
+impl<T: Debug> Debug for DList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::rc::Rc;
+
+    fn items<T: Clone>(list: &DList<T>) -> Vec<T> {
+        let forward: Vec<T> = list.iter().cloned().collect();
+        let mut backward: Vec<T> = list.iter().rev().cloned().collect();
+        backward.reverse();
+        assert_eq!(forward.len(), list.len());
+        assert_eq!(backward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut list = DList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(items(&list), [1, 2, 3]);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+        *list.front_mut().unwrap() = 10;
+        *list.back_mut().unwrap() = 30;
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(2));
+        assert!(list.is_empty());
+        assert_eq!(list.front(), None);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list: DList<_> = (1..=5).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.collect::<Vec<_>>(), [&2, &3, &4]);
+
+        let mut iter = list.iter_mut();
+        let a = iter.next().unwrap();
+        let b = iter.next_back().unwrap();
+        *a += 10;
+        *b += 10;
+        assert_eq!(items(&list), [11, 2, 3, 4, 15]);
+        assert_eq!(format!("{list:?}"), "[11, 2, 3, 4, 15]");
+
+        let mut into = list.into_iter();
+        assert_eq!(into.next_back(), Some(15));
+        assert_eq!(into.collect::<Vec<_>>(), [11, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cursor_moves_and_wraps() {
+        let mut list: DList<_> = (0..3).collect();
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 0));
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.peek_next(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 0));
+        assert_eq!(cursor.peek_prev(), Some(&mut 2));
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(2));
+
+        let mut empty = DList::<u8>::new();
+        let mut cursor = empty.cursor_back_mut();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn cursor_insert() {
+        let mut list: DList<_> = [1, 3].into_iter().collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_after(2);
+        cursor.insert_before(0);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        // on the ghost: after means front, before means back
+        cursor.insert_after(-1);
+        cursor.insert_before(4);
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(5));
+        assert_eq!(items(&list), [-1, 0, 1, 2, 3, 4]);
+
+        let mut list = DList::new();
+        list.cursor_front_mut().insert_before(7);
+        assert_eq!(items(&list), [7]);
+    }
+
+    #[test]
+    fn cursor_remove() {
+        let mut list: DList<_> = (0..5).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(1));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(items(&list), [2, 3]);
+        assert_eq!(list.back(), Some(&3));
+    }
+
+    #[test]
+    fn cursor_splice_after() {
+        let mut list: DList<_> = [1, 4].into_iter().collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after([2, 3].into_iter().collect());
+        cursor.splice_after(DList::new());
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_prev();
+        cursor.splice_after([-1, 0].into_iter().collect());
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(5));
+        cursor.splice_after([5].into_iter().collect());
+        assert_eq!(items(&list), [-1, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(list.len(), 7);
+    }
+
+    #[test]
+    fn cursor_split_after() {
+        let mut list: DList<_> = (0..6).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        let rest = cursor.split_after();
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(items(&rest), [2, 3, 4, 5]);
+        assert_eq!(items(&list), [0, 1]);
+
+        let mut cursor = list.cursor_back_mut();
+        assert!(cursor.split_after().is_empty());
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(items(&all), [0, 1]);
+        assert!(list.is_empty());
+        list.push_back(9);
+        assert_eq!(items(&list), [9]);
+    }
+
+    #[test]
+    fn drops_every_item() {
+        let item = Rc::new(());
+        let mut list = DList::new();
+        for _ in 0..6 {
+            list.push_back(item.clone());
+        }
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        drop(cursor.remove_current());
+        let rest = cursor.split_after();
+        cursor.splice_after(rest);
+        let mut other = DList::new();
+        other.push_back(item.clone());
+        cursor.splice_after(other);
+        assert_eq!(Rc::strong_count(&item), 7);
+        assert_eq!(list.iter_mut().rev().count(), 6);
+        drop(list);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    // random cursor edits checked against a Vec and a plain index
+    #[test]
+    fn matches_vec() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut list = DList::new();
+        let mut model: Vec<u32> = Vec::new();
+        // model cursor: Some(i) on an element, None on the ghost
+        let mut pos: Option<usize> = None;
+        for step in 0..300 {
+            let mut cursor = list.cursor_front_mut();
+            match pos {
+                Some(i) => (0..i).for_each(|_| cursor.move_next()),
+                None => cursor.move_prev(),
+            }
+            assert_eq!(cursor.index(), pos);
+            match rng.gen_range(0, 7) {
+                0 => {
+                    cursor.insert_after(step);
+                    model.insert(pos.map_or(0, |i| i + 1), step);
+                }
+                1 => {
+                    cursor.insert_before(step);
+                    match pos {
+                        Some(i) => {
+                            model.insert(i, step);
+                            pos = Some(i + 1);
+                        }
+                        None => model.push(step),
+                    }
+                }
+                2 => {
+                    let expected = pos.map(|i| model.remove(i));
+                    assert_eq!(cursor.remove_current(), expected);
+                    if pos.is_some_and(|i| i == model.len()) {
+                        pos = None;
+                    }
+                }
+                3 => {
+                    let other: DList<_> = (0..rng.gen_range(0, 3)).map(|i| step + i).collect();
+                    let at = pos.map_or(0, |i| i + 1);
+                    let added: Vec<_> = other.iter().copied().collect();
+                    cursor.splice_after(other);
+                    model.splice(at..at, added);
+                }
+                4 => {
+                    let rest = cursor.split_after();
+                    let at = pos.map_or(0, |i| i + 1);
+                    assert_eq!(items(&rest), model.split_off(at));
+                }
+                5 => {
+                    cursor.move_next();
+                    pos = match pos {
+                        Some(i) if i + 1 < model.len() => Some(i + 1),
+                        Some(_) => None,
+                        None if model.is_empty() => None,
+                        None => Some(0),
+                    };
+                }
+                _ => {
+                    cursor.move_prev();
+                    pos = match pos {
+                        Some(0) => None,
+                        Some(i) => Some(i - 1),
+                        None => model.len().checked_sub(1),
+                    };
+                }
+            }
+            assert_eq!(cursor.index(), pos);
+            assert_eq!(items(&list), model);
+        }
+    }
+
+    #[test]
+    fn reverse_split_append() {
+        let mut list: DList<_> = (1..=5).collect();
+        list.reverse();
+        assert_eq!(items(&list), [5, 4, 3, 2, 1]);
+        let mut rest = list.split_at(2);
+        assert_eq!(items(&list), [5, 4]);
+        assert_eq!(items(&rest), [3, 2, 1]);
+        assert!(rest.split_at(3).is_empty());
+        list.append(&mut rest);
+        assert!(rest.is_empty());
+        assert_eq!(items(&list), [5, 4, 3, 2, 1]);
+        let mut all = list.split_at(0);
+        assert!(list.is_empty());
+        list.append(&mut all);
+        assert_eq!(items(&list), [5, 4, 3, 2, 1]);
+
+        let mut empty = DList::<i32>::new();
+        empty.reverse();
+        empty.append(&mut DList::new());
+        assert!(empty.split_at(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 4 out of bounds for length 3")]
+    fn split_at_past_the_end() {
+        (0..3).collect::<DList<_>>().split_at(4);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut list: DList<_> = [(2, 'a'), (1, 'b'), (2, 'c'), (0, 'd'), (1, 'e')]
+            .into_iter()
+            .collect();
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            items(&list).iter().map(|x| x.1).collect::<String>(),
+            "dbeac"
+        );
+        list.sort_by(|a, b| b.0.cmp(&a.0));
+        assert_eq!(
+            items(&list).iter().map(|x| x.1).collect::<String>(),
+            "acbed"
+        );
+    }
+
+    #[test]
+    fn sort_matches_vec() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for len in 0..70 {
+            let model: Vec<u8> = (0..len).map(|_| rng.gen_range(0, 10)).collect();
+            let mut list: DList<_> = model.iter().copied().collect();
+            list.sort();
+            let mut sorted = model.clone();
+            sorted.sort();
+            assert_eq!(items(&list), sorted);
+            list.push_back(0);
+            assert_eq!(list.len(), len + 1);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // the leak is the point
+    fn panicking_sort_leaks_instead_of_breaking() {
+        use std::panic::{AssertUnwindSafe, catch_unwind};
+        let mut list: DList<_> = (0..10).rev().collect();
+        let mut calls = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a: &i32, b| {
+                calls += 1;
+                assert!(calls < 5, "bad comparison");
+                a.cmp(b)
+            })
+        }));
+        assert!(result.is_err());
+        assert!(list.is_empty());
+        list.push_back(1);
+        assert_eq!(items(&list), [1]);
+    }
+
+    #[test]
+    fn merge_dedup_retain() {
+        let mut list: DList<_> = [1, 3, 3, 8].into_iter().collect();
+        list.merge_sorted([0, 3, 4, 9, 9].into_iter().collect());
+        assert_eq!(items(&list), [0, 1, 3, 3, 3, 4, 8, 9, 9]);
+        list.dedup();
+        assert_eq!(items(&list), [0, 1, 3, 4, 8, 9]);
+        list.retain(|x| x % 2 == 0);
+        assert_eq!(items(&list), [0, 4, 8]);
+        list.merge_sorted(DList::new());
+        assert_eq!(items(&list), [0, 4, 8]);
+        list.retain(|_| false);
+        assert!(list.is_empty());
+        list.merge_sorted([2, 2].into_iter().collect());
+        assert_eq!(items(&list), [2, 2]);
+    }
+
+    #[test]
+    fn node_handles() {
+        let mut list = DList::new();
+        let b = list.push_back_node('b');
+        let a = list.push_front_node('a');
+        let d = list.push_back_node('d');
+        assert_eq!(list.front_node(), Some(a));
+        assert_eq!(list.back_node(), Some(d));
+        // SAFETY: every handle used below is still linked in `list`
+        unsafe {
+            let c = list.insert_after_node(b, 'c');
+            assert_eq!(items(&list), ['a', 'b', 'c', 'd']);
+            assert_eq!(list.next_node(b), Some(c));
+            assert_eq!(list.prev_node(b), Some(a));
+            assert_eq!(list.next_node(d), None);
+
+            list.move_to_front(c);
+            assert_eq!(items(&list), ['c', 'a', 'b', 'd']);
+            list.move_to_front(c);
+            list.move_to_back(a);
+            assert_eq!(items(&list), ['c', 'b', 'd', 'a']);
+            list.move_to_front(a);
+            list.move_to_back(c);
+            assert_eq!(items(&list), ['a', 'b', 'd', 'c']);
+
+            *list.node_mut(d) = 'D';
+            assert_eq!(*list.node(d), 'D');
+            assert_eq!(list.remove_node(b), 'b');
+            assert_eq!(list.remove_node(c), 'c');
+            assert_eq!(items(&list), ['a', 'D']);
+            assert_eq!(list.len(), 2);
+            assert_eq!(list.remove_node(a), 'a');
+            list.move_to_back(d);
+            list.move_to_front(d);
+            assert_eq!(list.remove_node(d), 'D');
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.front_node(), None);
+        assert_eq!(list.back_node(), None);
+    }
+}
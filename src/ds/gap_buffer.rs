@@ -0,0 +1,437 @@
+use std::{
+    fmt::{self, Debug, Display},
+    mem::MaybeUninit,
+    ptr, slice,
+};
+
+use crate::growth::{Doubling, GrowthPolicy};
+
+/*
+ * GapBuffer
+ * One block with an unused gap sitting at the cursor. Typing fills the
+ * gap from the left, backspace/delete widen it again, so edits at the
+ * cursor never shift the rest of the text.
+ *
+ *   text "hello world", cursor after "hello"
+ *   [ h e l l o | _ _ _ _ | ␣ w o r l d ]
+ *                ^gap_start ^gap_end
+ *
+ * insert/delete at the cursor are O(1) (amortized when the gap runs out).
+ * Moving the cursor by d slides d items across the gap, O(d), which is
+ * cheap for the short hops an editor makes between keystrokes.
+ * get/iter translate logical indexes around the gap.
+ *
+ * GapBuffer<char> adds text helpers: insert_str, line/column lookups and
+ * line iteration.
+ * */
+
+pub struct GapBuffer<T> {
+    buf: Box<[MaybeUninit<T>]>,
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl<T> Default for GapBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> GapBuffer<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        GapBuffer {
+            buf: Box::new_uninit_slice(capacity),
+            gap_start: 0,
+            gap_end: capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.capacity() - self.gap_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn gap_len(&self) -> usize {
+        self.gap_end - self.gap_start
+    }
+
+    /// Position of the cursor, between `cursor() - 1` and `cursor()`.
+    pub fn cursor(&self) -> usize {
+        self.gap_start
+    }
+
+    /// Moves the gap so the cursor sits before item `pos`.
+    ///
+    /// # Panics
+    ///
+    /// If `pos > len`.
+    pub fn set_cursor(&mut self, pos: usize) {
+        let len = self.len();
+        assert!(pos <= len, "cursor {pos} out of bounds for length {len}");
+        let base = self.buf.as_mut_ptr();
+        // SAFETY: both ranges are inside the block, ptr::copy allows them
+        // to overlap, and the slots left behind become part of the gap
+        unsafe {
+            if pos < self.gap_start {
+                let count = self.gap_start - pos;
+                ptr::copy(base.add(pos), base.add(self.gap_end - count), count);
+                self.gap_start -= count;
+                self.gap_end -= count;
+            } else if pos > self.gap_start {
+                let count = pos - self.gap_start;
+                ptr::copy(base.add(self.gap_end), base.add(self.gap_start), count);
+                self.gap_start += count;
+                self.gap_end += count;
+            }
+        }
+    }
+
+    /// Moves the cursor one item left, false if it is already at the start.
+    pub fn move_left(&mut self) -> bool {
+        if self.gap_start == 0 {
+            return false;
+        }
+        self.set_cursor(self.gap_start - 1);
+        true
+    }
+
+    /// Moves the cursor one item right, false if it is already at the end.
+    pub fn move_right(&mut self) -> bool {
+        if self.gap_end == self.capacity() {
+            return false;
+        }
+        self.set_cursor(self.gap_start + 1);
+        true
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        if additional <= self.gap_len() {
+            return;
+        }
+        let required = self
+            .len()
+            .checked_add(additional)
+            .expect("GapBuffer capacity overflow");
+        let new_cap = Doubling.grow(self.capacity(), required);
+        let mut buf = Box::new_uninit_slice(new_cap);
+        let tail = self.capacity() - self.gap_end;
+        // SAFETY: the text before and after the gap is copied to the start
+        // and the end of the new block, the old block is freed without
+        // dropping anything
+        unsafe {
+            let src = self.buf.as_ptr();
+            let dst = buf.as_mut_ptr();
+            ptr::copy_nonoverlapping(src, dst, self.gap_start);
+            ptr::copy_nonoverlapping(src.add(self.gap_end), dst.add(new_cap - tail), tail);
+        }
+        self.buf = buf;
+        self.gap_end = new_cap - tail;
+    }
+
+    /// Inserts before the cursor and moves the cursor past the item.
+    pub fn insert(&mut self, item: T) {
+        self.reserve(1);
+        self.buf[self.gap_start].write(item);
+        self.gap_start += 1;
+    }
+
+    /// Removes the item before the cursor (backspace).
+    pub fn delete_backward(&mut self) -> Option<T> {
+        if self.gap_start == 0 {
+            return None;
+        }
+        self.gap_start -= 1;
+        // SAFETY: the slot was initialized and is now part of the gap
+        Some(unsafe { self.buf[self.gap_start].assume_init_read() })
+    }
+
+    /// Removes the item after the cursor (delete).
+    pub fn delete_forward(&mut self) -> Option<T> {
+        if self.gap_end == self.capacity() {
+            return None;
+        }
+        self.gap_end += 1;
+        // SAFETY: the slot was initialized and is now part of the gap
+        Some(unsafe { self.buf[self.gap_end - 1].assume_init_read() })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let slot = if index < self.gap_start {
+            index
+        } else {
+            index + self.gap_len()
+        };
+        // SAFETY: slots outside the gap are initialized
+        Some(unsafe { self.buf[slot].assume_init_ref() })
+    }
+
+    /// Items before and after the cursor.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        // SAFETY: [0, gap_start) and [gap_end, capacity) are initialized
+        unsafe {
+            let base = self.buf.as_ptr() as *const T;
+            (
+                slice::from_raw_parts(base, self.gap_start),
+                slice::from_raw_parts(base.add(self.gap_end), self.capacity() - self.gap_end),
+            )
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (before, after) = self.as_slices();
+        before.iter().chain(after)
+    }
+}
+
+impl<T> Drop for GapBuffer<T> {
+    fn drop(&mut self) {
+        let tail = self.capacity() - self.gap_end;
+        let base = self.buf.as_mut_ptr() as *mut T;
+        // SAFETY: both sides of the gap are initialized and never used again
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(base, self.gap_start));
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(base.add(self.gap_end), tail));
+        }
+    }
+}
+
+impl<T> FromIterator<T> for GapBuffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buffer = GapBuffer::new();
+        for item in iter {
+            buffer.insert(item);
+        }
+        buffer
+    }
+}
+
+impl<T: Debug> Debug for GapBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GapBuffer")
+            .field("cursor", &self.cursor())
+            .field("items", &self.as_slices())
+            .finish()
+    }
+}
+
+impl GapBuffer<char> {
+    pub fn insert_str(&mut self, text: &str) {
+        self.reserve(text.chars().count());
+        for c in text.chars() {
+            self.insert(c);
+        }
+    }
+
+    /// Zero based (line, column) of the char at `pos`, columns count chars.
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let mut line = 0;
+        let mut col = 0;
+        for c in self.iter().take(pos) {
+            if *c == '\n' {
+                line += 1;
+                col = 0;
+            } else {
+                col += 1;
+            }
+        }
+        (line, col)
+    }
+
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        self.line_col(self.cursor())
+    }
+
+    /// Position of (line, col), the column is clamped to the line length.
+    /// None if the line does not exist.
+    pub fn pos_of(&self, line: usize, col: usize) -> Option<usize> {
+        let mut start = 0;
+        for _ in 0..line {
+            let newline = self.iter().skip(start).position(|c| *c == '\n')?;
+            start += newline + 1;
+        }
+        let line_len = self.iter().skip(start).take_while(|c| **c != '\n').count();
+        Some(start + col.min(line_len))
+    }
+
+    /// Moves the cursor to (line, col), false if the line does not exist.
+    pub fn set_cursor_line_col(&mut self, line: usize, col: usize) -> bool {
+        match self.pos_of(line, col) {
+            Some(pos) => {
+                self.set_cursor(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.iter().filter(|c| **c == '\n').count() + 1
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        let mut chars = self.iter();
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let mut line = String::new();
+            for c in chars.by_ref() {
+                if *c == '\n' {
+                    return Some(line);
+                }
+                line.push(*c);
+            }
+            done = true;
+            Some(line)
+        })
+    }
+}
+
+impl From<&str> for GapBuffer<char> {
+    fn from(text: &str) -> Self {
+        let mut buffer = GapBuffer::new();
+        buffer.insert_str(text);
+        buffer
+    }
+}
+
+impl Display for GapBuffer<char> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.iter() {
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn insert_and_delete_at_cursor() {
+        let mut buffer = GapBuffer::new();
+        for i in 0..5 {
+            buffer.insert(i);
+        }
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(buffer.cursor(), 5);
+        buffer.set_cursor(2);
+        buffer.insert(10);
+        assert_eq!(
+            buffer.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 10, 2, 3, 4]
+        );
+        assert_eq!(buffer.delete_forward(), Some(2));
+        assert_eq!(buffer.delete_backward(), Some(10));
+        assert_eq!(buffer.as_slices(), (&[0, 1][..], &[3, 4][..]));
+        assert_eq!(buffer.get(2), Some(&3));
+        assert_eq!(buffer.get(4), None);
+    }
+
+    #[test]
+    fn cursor_at_edges() {
+        let mut buffer: GapBuffer<_> = [1, 2].into_iter().collect();
+        assert!(!buffer.move_right());
+        assert_eq!(buffer.delete_forward(), None);
+        assert!(buffer.move_left());
+        assert!(buffer.move_left());
+        assert!(!buffer.move_left());
+        assert_eq!(buffer.delete_backward(), None);
+        assert_eq!(buffer.cursor(), 0);
+        buffer.insert(0);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cursor 4 out of bounds for length 3")]
+    fn cursor_out_of_bounds() {
+        let mut buffer: GapBuffer<_> = [1, 2, 3].into_iter().collect();
+        buffer.set_cursor(4);
+    }
+
+    #[test]
+    fn growth_keeps_both_sides() {
+        let mut buffer = GapBuffer::with_capacity(4);
+        buffer.insert('a');
+        buffer.insert('d');
+        buffer.move_left();
+        buffer.insert('b');
+        buffer.insert('c');
+        assert_eq!(buffer.capacity(), 4);
+        buffer.insert('x');
+        assert_eq!(buffer.capacity(), 8);
+        assert_eq!(buffer.to_string(), "abcxd");
+        assert_eq!(buffer.cursor(), 4);
+    }
+
+    #[test]
+    fn text_lines() {
+        let mut text = GapBuffer::from("fn main() {\n    hi\n}");
+        assert_eq!(text.line_count(), 3);
+        assert_eq!(text.cursor_line_col(), (2, 1));
+        assert_eq!(text.line_col(12), (1, 0));
+        assert_eq!(text.pos_of(1, 4), Some(16));
+        assert_eq!(text.pos_of(1, 99), Some(18));
+        assert_eq!(text.pos_of(3, 0), None);
+
+        assert!(text.set_cursor_line_col(1, 4));
+        text.delete_forward();
+        text.delete_forward();
+        text.insert_str("println!()");
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            ["fn main() {", "    println!()", "}"]
+        );
+        assert_eq!(text.cursor_line_col(), (1, 14));
+        assert_eq!(GapBuffer::from("").lines().count(), 1);
+        assert_eq!(
+            GapBuffer::from("a\n").lines().collect::<Vec<_>>(),
+            ["a", ""]
+        );
+    }
+
+    #[test]
+    fn typing_session() {
+        let mut text = GapBuffer::from("hello world");
+        text.set_cursor(5);
+        text.insert_str(",");
+        text.set_cursor(text.len());
+        text.insert_str("!");
+        text.set_cursor(0);
+        text.delete_forward();
+        text.insert('H');
+        assert_eq!(text.to_string(), "Hello, world!");
+    }
+
+    #[test]
+    fn drops_items() {
+        let item = Rc::new(());
+        let mut buffer = GapBuffer::new();
+        for _ in 0..10 {
+            buffer.insert(item.clone());
+        }
+        buffer.set_cursor(3);
+        drop(buffer.delete_forward());
+        drop(buffer.delete_backward());
+        assert_eq!(Rc::strong_count(&item), 9);
+        drop(buffer);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+}
@@ -1,3 +1,17 @@
+use std::cmp::Ordering;
+
+/*
+ * List
+ * Singly linked list of boxed nodes, pushed and popped at the head.
+ *
+ *   head -> [ 3 ] -> [ 2 ] -> [ 1 ] -> None
+ *
+ * push/pop/peek are O(1). Everything else walks the chain:
+ * reverse, split_at, append, dedup and retain are O(n), sort is a
+ * bottom-up merge sort in O(n log n) and merge_sorted is O(n + m).
+ * They all relink the existing nodes, no item is moved or reallocated.
+ * */
+
 type Link<T> = Option<Box<Node<T>>>;
 
 pub struct List<T> {
@@ -28,12 +42,203 @@ impl<T> List<T> {
             node.elem
         })
     }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> ListIter<'_, T> {
+        ListIter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn reverse(&mut self) {
+        let mut rest = self.head.take();
+        while let Some(mut node) = rest {
+            rest = node.next.take();
+            node.next = self.head.take();
+            self.head = Some(node);
+        }
+    }
+
+    /// Keeps the first `at` items and returns the rest.
+    ///
+    /// # Panics
+    ///
+    /// If `at` is greater than the length.
+    pub fn split_at(&mut self, at: usize) -> List<T> {
+        let mut link = &mut self.head;
+        for len in 0..at {
+            match link {
+                Some(node) => link = &mut node.next,
+                None => panic!("index {at} out of bounds for length {len}"),
+            }
+        }
+        List { head: link.take() }
+    }
+
+    /// Moves every item of `other` to the end, leaving it empty.
+    pub fn append(&mut self, other: &mut List<T>) {
+        let mut link = &mut self.head;
+        while let Some(node) = link {
+            link = &mut node.next;
+        }
+        *link = other.head.take();
+    }
+
+    /// Stable, equal items keep their order.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut cmp: F) {
+        let len = self.iter().count();
+        // merge neighbouring runs of width 1, 2, 4, ... until one is left
+        let mut width = 1;
+        while width < len {
+            let mut rest = self.head.take();
+            let mut tail = &mut self.head;
+            while rest.is_some() {
+                let mut left = rest;
+                let mut right = cut(&mut left, width);
+                rest = cut(&mut right, width);
+                *tail = merge(left, right, &mut cmp);
+                while let Some(node) = tail {
+                    tail = &mut node.next;
+                }
+            }
+            width *= 2;
+        }
+    }
+
+    /// Merges two sorted lists into one sorted list. On ties items of
+    /// `self` come first.
+    pub fn merge_sorted(&mut self, mut other: List<T>)
+    where
+        T: Ord,
+    {
+        self.head = merge(self.head.take(), other.head.take(), &mut T::cmp);
+    }
+
+    /// Removes consecutive repeated items, like [`Vec::dedup`].
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        let mut current = self.head.as_deref_mut();
+        while let Some(node) = current {
+            while let Some(mut next) = node.next.take_if(|next| next.elem == node.elem) {
+                node.next = next.next.take();
+            }
+            current = node.next.as_deref_mut();
+        }
+    }
+
+    /// Keeps only the items `f` returns true for, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut rest = self.head.take();
+        let mut tail = &mut self.head;
+        while let Some(mut node) = rest {
+            rest = node.next.take();
+            if f(&node.elem) {
+                tail = &mut tail.insert(node).next;
+            }
+        }
+    }
+}
+
+// Cuts the chain after `n` nodes and returns what came after.
+fn cut<T>(mut link: &mut Link<T>, n: usize) -> Link<T> {
+    for _ in 0..n {
+        match link {
+            Some(node) => link = &mut node.next,
+            None => return None,
+        }
+    }
+    link.take()
+}
+
+// Stable merge of two sorted chains, ties are taken from `a`.
+fn merge<T, F>(mut a: Link<T>, mut b: Link<T>, cmp: &mut F) -> Link<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut head = None;
+    let mut tail = &mut head;
+    loop {
+        let from = match (&a, &b) {
+            (Some(x), Some(y)) if cmp(&y.elem, &x.elem) == Ordering::Less => &mut b,
+            (Some(_), Some(_)) => &mut a,
+            _ => break,
+        };
+        let mut node = from.take().expect("checked above");
+        *from = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    *tail = a.or(b);
+    head
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the default drop would recurse once per node
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut current = self.head.take();
+        while let Some(mut node) = current {
+            current = node.next.take();
+        }
+    }
+}
+
+pub struct ListIter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for ListIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn list_of<T: Clone>(items: &[T]) -> List<T> {
+        let mut list = List::new();
+        for item in items.iter().rev() {
+            list.push(item.clone());
+        }
+        list
+    }
+
+    fn items<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
     #[test]
     fn push() {
         let mut list = List::<i32>::new();
@@ -43,6 +248,110 @@ mod tests {
         assert_eq!(list.pop(), Some(4));
         assert_eq!(list.pop(), Some(3));
         assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+    }
+
+    #[test]
+    fn peek_and_iter() {
+        let mut list = List::new();
+        assert_eq!(list.peek(), None);
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.peek(), Some(&2));
+        if let Some(top) = list.peek_mut() {
+            *top = 20;
+        }
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&20, &1]);
+        assert!(!list.is_empty());
+    }
+
+    #[test]
+    fn long_list_drops() {
+        let mut list = List::new();
+        for i in 0..100_000 {
+            list.push(i);
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn reverse_split_append() {
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        list.reverse();
+        assert_eq!(items(&list), [5, 4, 3, 2, 1]);
+        let mut rest = list.split_at(2);
+        assert_eq!(items(&list), [5, 4]);
+        assert_eq!(items(&rest), [3, 2, 1]);
+        assert!(rest.split_at(3).is_empty());
+        list.append(&mut rest);
+        assert!(rest.is_empty());
+        assert_eq!(items(&list), [5, 4, 3, 2, 1]);
+        let all = list.split_at(0);
+        assert!(list.is_empty());
+        list.append(&mut list_of(&[7]));
+        assert_eq!(items(&list), [7]);
+        assert_eq!(items(&all), [5, 4, 3, 2, 1]);
+
+        let mut empty = List::<i32>::new();
+        empty.reverse();
+        assert!(empty.split_at(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 4 out of bounds for length 3")]
+    fn split_at_past_the_end() {
+        list_of(&[1, 2, 3]).split_at(4);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut list = list_of(&[(2, 'a'), (1, 'b'), (2, 'c'), (0, 'd'), (1, 'e')]);
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        let tags: String = list.iter().map(|x| x.1).collect();
+        assert_eq!(tags, "dbeac");
+        list.sort_by(|a, b| b.0.cmp(&a.0));
+        let tags: String = list.iter().map(|x| x.1).collect();
+        assert_eq!(tags, "acbed");
+    }
+
+    #[test]
+    fn sort_matches_vec() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for len in 0..70 {
+            let model: Vec<u8> = (0..len).map(|_| rng.gen_range(0, 10)).collect();
+            let mut list = list_of(&model);
+            list.sort();
+            let mut sorted = model.clone();
+            sorted.sort();
+            assert_eq!(items(&list), sorted);
+        }
+    }
+
+    #[test]
+    fn merge_dedup_retain() {
+        let mut list = list_of(&[1, 3, 3, 8]);
+        list.merge_sorted(list_of(&[0, 3, 4, 9, 9]));
+        assert_eq!(items(&list), [0, 1, 3, 3, 3, 4, 8, 9, 9]);
+        list.dedup();
+        assert_eq!(items(&list), [0, 1, 3, 4, 8, 9]);
+        list.retain(|x| x % 2 == 0);
+        assert_eq!(items(&list), [0, 4, 8]);
+        list.merge_sorted(List::new());
+        assert_eq!(items(&list), [0, 4, 8]);
+        list.retain(|_| false);
+        assert!(list.is_empty());
+        list.merge_sorted(list_of(&[2, 2]));
+        assert_eq!(items(&list), [2, 2]);
+    }
+
+    #[test]
+    fn sort_long_list() {
+        let mut list = List::new();
+        for i in 0..100_000 {
+            list.push(i % 1000);
+        }
+        list.sort();
+        assert!(items(&list).is_sorted());
     }
 
     //    #[test]
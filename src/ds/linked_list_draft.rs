@@ -2,80 +2,80 @@ use core::fmt;
 use std::{
     cell::{Ref, RefCell, RefMut},
     fmt::{Display, Formatter},
-    rc::Rc,
+    marker::PhantomData,
+    rc::{Rc, Weak},
 };
 
 /*
-* head                        tail
-    |                           |
-    v                           v
-+--------+   +--------+   +--------+
-|        |   |        |   |        |
-| node 0 |-->| node 1 |-->| node 2 |--> NULL
-|        |   |        |   |        |
-+--------+   +--------+   +--------+
+* head                                    tail
+    |                                       |
+    v                                       v
++--------+      +--------+      +--------+
+|        |----->|        |----->|        |--> NULL
+| node 0 |      | node 1 |      | node 2 |
+|        |<- - -|        |<- - -|        |
++--------+      +--------+      +--------+
+*  next links are Rc (they own the node), prev links are Weak so the
+*  two directions do not form a reference cycle and nodes are freed
+*  as soon as they are unlinked.
+*
 *  Implementations
-*  1. push_front
+*  1. push_front / push_back
 *   time complexity: O(1)
-*  2. pop
+*  2. pop_front / pop_back
 *    time complexity: O(1)
 *  3. get
-*    time complexity: O(n)
-*  4. peek
-*    time complexity: O(1)
-*  5. peek_mut
+*    time complexity: O(n), walks from the closer end
+*  4. peek / peek_back (and _mut)
 *    time complexity: O(1)
+*  5. iter / iter_mut / into_iter
+*    double ended, yield Ref<T> / RefMut<T> / T
+*  6. find_cycle / has_cycle
+*    time complexity: O(n), space O(1) (Floyd's tortoise and hare)
 * */
-#[derive(Debug, Clone)]
-pub struct Node<T> {
+#[derive(Debug)]
+struct Node<T> {
     data: T,
-    next: Option<Rc<RefCell<Node<T>>>>,
+    next: Option<NodeRef<T>>,
+    prev: Option<Weak<RefCell<Node<T>>>>,
 }
 
+type NodeRef<T> = Rc<RefCell<Node<T>>>;
+
 impl<T> Node<T> {
-    pub fn new(data: T) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Node { data, next: None }))
+    fn new(data: T) -> NodeRef<T> {
+        Rc::new(RefCell::new(Node {
+            data,
+            next: None,
+            prev: None,
+        }))
     }
 }
 
 pub struct LinkedList<T> {
-    head: Option<Rc<RefCell<Node<T>>>>,
-    tail: Option<Rc<RefCell<Node<T>>>>,
+    head: Option<NodeRef<T>>,
+    tail: Option<NodeRef<T>>,
     length: usize,
 }
 
-pub struct IntoIter<T>(LinkedList<T>);
-
-pub struct IterMut<T> {
-    next: Option<NodeRef<T>>,
-}
-
-type NodeRef<T> = Rc<RefCell<Node<T>>>;
-pub struct Iter<T> {
-    next: Option<NodeRef<T>>,
-}
-
-impl<T> Display for LinkedList<T>
-where
-    T: Display,
-{
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match &self.head {
-            Some(node) => write!(f, "{}", node.borrow()),
-            None => Ok(()),
-        }
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<T> Display for Node<T>
+impl<T> Display for LinkedList<T>
 where
     T: Display,
 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match &self.next {
-            Some(node) => write!(f, "{}, -> {}", self.data, node.borrow()),
-            None => write!(f, "{}", self.data),
+        for (i, data) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", -> ")?;
+            }
+            write!(f, "{}", *data)?;
         }
+        Ok(())
     }
 }
 
@@ -88,10 +88,19 @@ impl<T> LinkedList<T> {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     pub fn push_front(&mut self, data: T) {
         let new_head = Node::new(data);
         match self.head.take() {
             Some(head) => {
+                head.borrow_mut().prev = Some(Rc::downgrade(&new_head));
                 new_head.borrow_mut().next = Some(head);
                 self.head = Some(new_head);
             }
@@ -104,55 +113,83 @@ impl<T> LinkedList<T> {
     }
 
     pub fn push_back(&mut self, data: T) {
-        let new = Node::new(data);
+        let new_tail = Node::new(data);
         match self.tail.take() {
             Some(tail) => {
-                new.borrow_mut().next = Some(tail);
-                self.tail = Some(new);
+                new_tail.borrow_mut().prev = Some(Rc::downgrade(&tail));
+                tail.borrow_mut().next = Some(new_tail.clone());
+                self.tail = Some(new_tail);
             }
             None => {
-                self.tail = Some(new.clone()); // +1 new_head
-                self.head = Some(new);
+                self.head = Some(new_tail.clone()); // +1 new_tail
+                self.tail = Some(new_tail);
             }
         }
         self.length += 1;
     }
 
-    pub fn pop(&mut self) -> Option<T> {
+    pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
             match old_head.borrow_mut().next.take() {
                 Some(new_head) => {
+                    new_head.borrow_mut().prev = None;
                     self.head = Some(new_head);
                 }
                 None => {
                     self.tail.take();
                 }
             }
-            Rc::try_unwrap(old_head).ok().unwrap().into_inner().data
+            self.length -= 1;
+            Self::into_data(old_head)
         })
     }
 
-    pub fn get(&self, index: usize) -> Ref<T> {
-        let current = self.head.as_ref();
-        let mut i = 0;
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.take().map(|old_tail| {
+            let prev = old_tail.borrow_mut().prev.take();
+            match prev.and_then(|prev| prev.upgrade()) {
+                Some(new_tail) => {
+                    new_tail.borrow_mut().next = None;
+                    self.tail = Some(new_tail);
+                }
+                None => {
+                    self.head.take();
+                }
+            }
+            self.length -= 1;
+            Self::into_data(old_tail)
+        })
+    }
 
-        if index >= self.length {
-            panic!("index out of bound");
-        }
-        if index < i {
-            panic!("index negative not allowed");
+    // the list held the only strong references to an unlinked node
+    fn into_data(node: NodeRef<T>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(node) => node.into_inner().data,
+            Err(_) => unreachable!("unlinked node is still shared"),
         }
+    }
 
-        while let Some(node) = current {
-            if i == index {
-                return Ref::map(node.borrow(), |node| &node.data);
-            }
-            i += 1;
+    /// Same as [`LinkedList::pop_front`].
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    /// # Panics
+    ///
+    /// If `index >= len`.
+    pub fn get(&self, index: usize) -> Ref<'_, T> {
+        if index >= self.length {
+            panic!("index {index} out of bounds for length {}", self.length);
+        }
+        if index < self.length / 2 {
+            self.iter().nth(index)
+        } else {
+            self.iter().nth_back(self.length - 1 - index)
         }
-        panic!("index negative not allowed");
+        .expect("length is out of sync with the nodes")
     }
 
-    pub fn peek(&self) -> Option<Ref<T>> {
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
         self.head
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.data))
@@ -164,54 +201,206 @@ impl<T> LinkedList<T> {
             .map(|node| RefMut::map(node.borrow_mut(), |n| &mut n.data))
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.data))
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |n| &mut n.data))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
-            next: self.head.as_ref().cloned(),
+            front: self.head.as_ref().map(|node| cell(node)),
+            back: self.tail.as_ref().map(|node| cell(node)),
+            remaining: self.length,
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
-            next: self.head.as_ref().cloned(),
+            front: self.head.as_ref().map(|node| cell(node)),
+            back: self.tail.as_ref().map(|node| cell(node)),
+            remaining: self.length,
+            _list: PhantomData,
+        }
+    }
+
+    /// Index of the node where the next links loop back on themselves,
+    /// None if they end. The list never builds a cycle itself, this
+    /// checks code that rewires nodes by hand.
+    ///
+    /// Floyd: a slow walker takes one step while a fast one takes two.
+    /// If the chain loops they meet inside the loop, and the loop starts
+    /// as many steps from the head as from that meeting point.
+    pub fn find_cycle(&self) -> Option<usize> {
+        fn step<T>(node: &NodeRef<T>) -> Option<NodeRef<T>> {
+            node.borrow().next.clone()
+        }
+        let mut slow = self.head.clone()?;
+        let mut fast = slow.clone();
+        loop {
+            fast = step(&step(&fast)?)?;
+            slow = step(&slow)?;
+            if Rc::ptr_eq(&slow, &fast) {
+                break;
+            }
+        }
+        let mut start = self.head.clone()?;
+        let mut index = 0;
+        while !Rc::ptr_eq(&start, &slow) {
+            start = step(&start)?;
+            slow = step(&slow)?;
+            index += 1;
+        }
+        Some(index)
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+}
+
+// The iterators walk the nodes through plain references instead of
+// cloning Rcs. A node is owned by the list (head or its prev's next) for
+// as long as the list is borrowed, so the reference stays valid for 'a.
+fn cell<'a, T>(node: &NodeRef<T>) -> &'a RefCell<Node<T>> {
+    // SAFETY: see above, callers tie 'a to a borrow of the list
+    unsafe { &*Rc::as_ptr(node) }
+}
+
+fn next_cell<'a, T>(node: &RefCell<Node<T>>) -> Option<&'a RefCell<Node<T>>> {
+    node.borrow().next.as_ref().map(|next| cell(next))
+}
+
+fn prev_cell<'a, T>(node: &RefCell<Node<T>>) -> Option<&'a RefCell<Node<T>>> {
+    let prev = node.borrow().prev.as_ref()?.upgrade()?;
+    Some(cell(&prev))
+}
+
+pub struct Iter<'a, T> {
+    front: Option<&'a RefCell<Node<T>>>,
+    back: Option<&'a RefCell<Node<T>>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Ref<'a, T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
+        let node = self.front?;
+        self.front = next_cell(node);
+        self.remaining -= 1;
+        Some(Ref::map(node.borrow(), |node| &node.data))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back?;
+        self.back = prev_cell(node);
+        self.remaining -= 1;
+        Some(Ref::map(node.borrow(), |node| &node.data))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    front: Option<&'a RefCell<Node<T>>>,
+    back: Option<&'a RefCell<Node<T>>>,
+    remaining: usize,
+    _list: PhantomData<&'a mut LinkedList<T>>,
 }
 
-impl<'a, T> Iterator for IterMut<T> {
-    type Item = NodeRef<T>;
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = RefMut<'a, T>;
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(cur) = self.next.as_ref().cloned() {
-            self.next = cur.borrow_mut().next.clone();
-            return Some(cur);
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front?;
+        // read the link before handing out the RefMut, the caller may
+        // still hold it when asking for the next item
+        self.front = next_cell(node);
+        self.remaining -= 1;
+        Some(RefMut::map(node.borrow_mut(), |node| &mut node.data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
-        None
+        let node = self.back?;
+        self.back = prev_cell(node);
+        self.remaining -= 1;
+        Some(RefMut::map(node.borrow_mut(), |node| &mut node.data))
     }
 }
 
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T>(LinkedList<T>);
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.pop()
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.length, Some(self.0.length))
     }
 }
 
-impl<'a, T> Iterator for Iter<T> {
-    type Item = NodeRef<T>;
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(cur) = self.next.as_ref().cloned() {
-            self.next = cur.borrow().next.clone();
-            return Some(cur);
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        for data in iter {
+            list.push_back(data);
         }
-        None
+        list
     }
 }
 
 impl<T> Drop for LinkedList<T> {
     fn drop(&mut self) {
+        self.tail.take();
         let mut current = self.head.take();
         while let Some(node) = current {
             current = node.borrow_mut().next.take();
@@ -279,6 +468,10 @@ mod tests {
             let node = &*h;
             assert_eq!(node.data, 1);
         }
+
+        // head -> tail order, not the other way round
+        list.push_back(7);
+        assert_eq!(list.iter().map(|v| *v).collect::<Vec<_>>(), vec![1, 4, 7]);
     }
 
     #[test]
@@ -287,6 +480,12 @@ mod tests {
         list.push_front(10);
         let item = list.get(0);
         assert_eq!(*item, 10);
+        drop(item);
+
+        let list: LinkedList<_> = (0..7).collect();
+        for i in 0..7 {
+            assert_eq!(*list.get(i), i);
+        }
     }
 
     #[test]
@@ -299,6 +498,23 @@ mod tests {
         assert_eq!(item, None);
     }
 
+    #[test]
+    fn pop_both_ends() {
+        let mut list = LinkedList::new();
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+        assert!(list.head.is_none() && list.tail.is_none());
+        list.push_front(4);
+        assert_eq!(*list.peek_back().unwrap(), 4);
+        assert_eq!(list.len(), 1);
+    }
+
     #[test]
     #[should_panic]
     fn should_panic() {
@@ -317,10 +533,14 @@ mod tests {
         list.push_front(10);
         assert_eq!(&*list.peek().unwrap(), &10);
         assert_eq!(&*list.peek_mut().unwrap(), &mut 10);
-        list.peek_mut().map(|mut v| *v = 100);
+        if let Some(mut v) = list.peek_mut() {
+            *v = 100
+        }
         assert_eq!(&*list.peek_mut().unwrap(), &mut 100);
         assert_eq!(list.pop(), Some(100));
         assert_eq!(&*list.peek().unwrap(), &11);
+        *list.peek_back_mut().unwrap() = 12;
+        assert_eq!(&*list.peek().unwrap(), &12);
     }
 
     #[test]
@@ -331,11 +551,28 @@ mod tests {
         list.push_front(14);
         list.push_front(15);
         let mut iter = list.iter();
-        assert_eq!(iter.next().unwrap().borrow().data, 15);
-        assert_eq!(iter.next().unwrap().borrow().data, 14);
-        assert_eq!(iter.next().unwrap().borrow().data, 13);
-        //TODO: improve iter to return None
-        //assert_eq!(iter.next().unwrap().borrow().data, None);
+        assert_eq!(*iter.next().unwrap(), 15);
+        assert_eq!(*iter.next().unwrap(), 14);
+        assert_eq!(*iter.next().unwrap(), 13);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn iter_double_ended() {
+        let list: LinkedList<_> = (1..=5).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(*iter.next().unwrap(), 1);
+        assert_eq!(*iter.next_back().unwrap(), 5);
+        assert_eq!(*iter.next_back().unwrap(), 4);
+        assert_eq!(*iter.next().unwrap(), 2);
+        assert_eq!(*iter.next().unwrap(), 3);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+        assert_eq!(
+            list.iter().rev().map(|v| *v).collect::<Vec<_>>(),
+            vec![5, 4, 3, 2, 1]
+        );
     }
 
     #[test]
@@ -346,7 +583,65 @@ mod tests {
         list.push_front(14);
         list.push_front(15);
         let mut iter = list.iter_mut();
-        // TODO: fix iter_mut
-        assert_eq!(iter.next().unwrap().borrow_mut().data, 15);
+        assert_eq!(*iter.next().unwrap(), 15);
+        // holding on to several items at once is fine, they are distinct
+        let mut a = iter.next().unwrap();
+        let mut b = iter.next_back().unwrap();
+        *a += 100;
+        *b += 100;
+        assert!(iter.next().is_none());
+        drop((a, b));
+        for mut v in list.iter_mut().rev() {
+            *v *= 2;
+        }
+        assert_eq!(list.to_string(), "30, -> 228, -> 226");
+    }
+
+    #[test]
+    fn into_iter() {
+        let list: LinkedList<_> = (1..=4).collect();
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn frees_every_node() {
+        let item = Rc::new(());
+        let mut list = LinkedList::new();
+        for _ in 0..10 {
+            list.push_back(item.clone());
+            list.push_front(item.clone());
+        }
+        drop(list.pop_back());
+        assert_eq!(Rc::strong_count(&item), 20);
+        drop(list);
+        assert_eq!(Rc::strong_count(&item), 1);
+
+        // long lists drop without recursing
+        let n = if cfg!(miri) { 1_000 } else { 100_000 };
+        let list: LinkedList<_> = (0..n).collect();
+        drop(list);
+    }
+
+    #[test]
+    fn floyd_cycle_detection() {
+        let list: LinkedList<_> = (0..6).collect();
+        assert_eq!(list.find_cycle(), None);
+        assert!(!LinkedList::<i32>::new().has_cycle());
+
+        let tail = list.tail.clone().unwrap();
+        for target in 0..6 {
+            // point the tail back at node `target`
+            let node = list.head.clone().unwrap();
+            let node = (0..target).fold(node, |node, _| node.borrow().next.clone().unwrap());
+            tail.borrow_mut().next = Some(node);
+            assert_eq!(list.find_cycle(), Some(target));
+            assert!(list.has_cycle());
+            tail.borrow_mut().next = None;
+        }
+        assert!(!list.has_cycle());
+        assert_eq!(list.iter().count(), 6);
     }
 }
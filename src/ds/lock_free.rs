@@ -0,0 +1,614 @@
+use std::{
+    marker::PhantomData,
+    mem::{ManuallyDrop, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+/*
+ * AtomicStack / AtomicQueue
+ * Lock free linked structures that any number of threads can push to and
+ * pop from through a shared reference.
+ *
+ * AtomicStack is a Treiber stack: the single threaded List with its head
+ * swapped by compare_exchange instead of assignment.
+ *
+ *   head --CAS--> [ c ] -> [ b ] -> [ a ] -> null
+ *
+ * AtomicQueue is a Michael-Scott queue: a singly linked list with a dummy
+ * node in front. Producers link at the tail, consumers swing the head.
+ * A lagging tail is helped forward by whoever notices it.
+ *
+ *   head -> [ dummy ] -> [ a ] -> [ b ] <- tail
+ *
+ * Both are O(1) per operation when uncontended; under contention a failed
+ * compare_exchange retries.
+ *
+ * Reclamation: a popped node can not be freed right away, another thread
+ * may have loaded the same pointer and be about to read its next link.
+ * Threads announce the node they are about to read in a hazard pointer,
+ * unlinked nodes are retired instead of freed, and every SCAN_THRESHOLD
+ * retirements the nodes no hazard points at are freed. A node can not be
+ * freed and reused while it is protected, which also rules out ABA on the
+ * compare_exchange.
+ * */
+
+const SCAN_THRESHOLD: usize = 64;
+
+// One published pointer. Slots are claimed by a thread for the length of
+// an operation and are only freed with the domain.
+struct HazardSlot {
+    ptr: AtomicPtr<()>,
+    active: AtomicBool,
+    next: *mut HazardSlot,
+}
+
+struct Retired {
+    ptr: *mut (),
+    free: unsafe fn(*mut ()),
+    next: *mut Retired,
+}
+
+unsafe fn free_box<T>(ptr: *mut ()) {
+    // SAFETY: only called on pointers that came from Box::into_raw::<T>
+    drop(unsafe { Box::from_raw(ptr.cast::<T>()) });
+}
+
+// Hazard pointers and retired nodes of one structure.
+struct Domain {
+    slots: AtomicPtr<HazardSlot>,
+    retired: AtomicPtr<Retired>,
+    retired_count: AtomicUsize,
+}
+
+impl Domain {
+    fn new() -> Self {
+        Domain {
+            slots: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+        }
+    }
+
+    // Claims a free slot, or adds one if all are taken.
+    fn hazard(&self) -> Hazard<'_> {
+        let mut slot = self.slots.load(Ordering::Acquire);
+        while !slot.is_null() {
+            // SAFETY: slots are only freed when the domain is dropped
+            let s = unsafe { &*slot };
+            if !s.active.load(Ordering::Relaxed)
+                && s.active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return Hazard { slot: s };
+            }
+            slot = s.next;
+        }
+        let slot = Box::into_raw(Box::new(HazardSlot {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.slots.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: the slot is not published yet
+            unsafe { (*slot).next = head };
+            match self
+                .slots
+                .compare_exchange_weak(head, slot, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        // SAFETY: published slots live as long as the domain
+        Hazard {
+            slot: unsafe { &*slot },
+        }
+    }
+
+    // Frees `ptr` (a Box<T>) once no hazard points at it.
+    //
+    // SAFETY: ptr must come from Box::into_raw, be unreachable for new
+    // readers and be retired only once.
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        let retired = Box::into_raw(Box::new(Retired {
+            ptr: ptr.cast(),
+            free: free_box::<T>,
+            next: ptr::null_mut(),
+        }));
+        // counted before it is pushed, so a concurrent scan that frees it
+        // never takes the count below zero
+        let count = self.retired_count.fetch_add(1, Ordering::Relaxed) + 1;
+        // SAFETY: retired was just allocated
+        unsafe { self.push_retired(retired) };
+        if count >= SCAN_THRESHOLD {
+            self.scan();
+        }
+    }
+
+    unsafe fn push_retired(&self, retired: *mut Retired) {
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: retired is owned by the caller until published
+            unsafe { (*retired).next = head };
+            match self.retired.compare_exchange_weak(
+                head,
+                retired,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    // Frees every retired node that is not protected, puts the rest back.
+    fn scan(&self) {
+        let mut retired = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+        if retired.is_null() {
+            return;
+        }
+        // pairs with the SeqCst store in Hazard::protect: a hazard set
+        // before the node was unlinked is seen here
+        std::sync::atomic::fence(Ordering::SeqCst);
+        let mut hazards = Vec::new();
+        let mut slot = self.slots.load(Ordering::Acquire);
+        while !slot.is_null() {
+            // SAFETY: slots live as long as the domain
+            let s = unsafe { &*slot };
+            let ptr = s.ptr.load(Ordering::SeqCst);
+            if !ptr.is_null() {
+                hazards.push(ptr);
+            }
+            slot = s.next;
+        }
+        let mut freed = 0;
+        while !retired.is_null() {
+            // SAFETY: the swap above gave this thread the whole list
+            unsafe {
+                let next = (*retired).next;
+                if hazards.contains(&(*retired).ptr) {
+                    self.push_retired(retired);
+                } else {
+                    let retired = Box::from_raw(retired);
+                    (retired.free)(retired.ptr);
+                    freed += 1;
+                }
+                retired = next;
+            }
+        }
+        self.retired_count.fetch_sub(freed, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Domain {
+    fn drop(&mut self) {
+        // &mut self: no thread holds a hazard anymore
+        let mut retired = *self.retired.get_mut();
+        while !retired.is_null() {
+            // SAFETY: every retired entry is owned by the domain now
+            unsafe {
+                let entry = Box::from_raw(retired);
+                (entry.free)(entry.ptr);
+                retired = entry.next;
+            }
+        }
+        let mut slot = *self.slots.get_mut();
+        while !slot.is_null() {
+            // SAFETY: as above
+            let s = unsafe { Box::from_raw(slot) };
+            slot = s.next;
+        }
+    }
+}
+
+struct Hazard<'a> {
+    slot: &'a HazardSlot,
+}
+
+impl Hazard<'_> {
+    // Loads `src` and publishes it, retrying until the published value is
+    // still current. While published the node will not be freed.
+    fn protect<T>(&self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Ordering::Relaxed);
+        loop {
+            self.slot.ptr.store(ptr.cast(), Ordering::SeqCst);
+            let current = src.load(Ordering::SeqCst);
+            if current == ptr {
+                return ptr;
+            }
+            ptr = current;
+        }
+    }
+
+    fn clear(&self) {
+        self.slot.ptr.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl Drop for Hazard<'_> {
+    fn drop(&mut self) {
+        self.clear();
+        self.slot.active.store(false, Ordering::Release);
+    }
+}
+
+struct StackNode<T> {
+    elem: ManuallyDrop<T>,
+    next: *mut StackNode<T>,
+}
+
+pub struct AtomicStack<T> {
+    head: AtomicPtr<StackNode<T>>,
+    domain: Domain,
+    _owns: PhantomData<T>,
+}
+
+// SAFETY: items only move in and out by value, no &T is ever shared
+// between threads, so T: Send is enough for both
+unsafe impl<T: Send> Send for AtomicStack<T> {}
+unsafe impl<T: Send> Sync for AtomicStack<T> {}
+
+impl<T> Default for AtomicStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> AtomicStack<T> {
+    pub fn new() -> Self {
+        AtomicStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            domain: Domain::new(),
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(StackNode {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: the node is not published yet
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let hazard = self.domain.hazard();
+        loop {
+            let head = hazard.protect(&self.head);
+            if head.is_null() {
+                return None;
+            }
+            // SAFETY: head is protected, so it is not freed, and next never
+            // changes once a node is published
+            let next = unsafe { (*head).next };
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                hazard.clear();
+                // SAFETY: winning the exchange makes this thread the only
+                // one that takes the item; the node is retired, not dropped
+                unsafe {
+                    let elem = ptr::read(&(*head).elem);
+                    self.domain.retire(head);
+                    return Some(ManuallyDrop::into_inner(elem));
+                }
+            }
+        }
+    }
+
+    /// A snapshot, other threads may change it right after.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Drop for AtomicStack<T> {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            // SAFETY: &mut self, the nodes still linked own their items
+            unsafe {
+                let mut boxed = Box::from_raw(node);
+                ManuallyDrop::drop(&mut boxed.elem);
+                node = boxed.next;
+            }
+        }
+    }
+}
+
+struct QueueNode<T> {
+    // uninit in the dummy, which is the node whose item was taken last
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<QueueNode<T>>,
+}
+
+impl<T> QueueNode<T> {
+    fn new(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(QueueNode {
+            elem,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+pub struct AtomicQueue<T> {
+    head: AtomicPtr<QueueNode<T>>,
+    tail: AtomicPtr<QueueNode<T>>,
+    domain: Domain,
+    _owns: PhantomData<T>,
+}
+
+// SAFETY: as for AtomicStack
+unsafe impl<T: Send> Send for AtomicQueue<T> {}
+unsafe impl<T: Send> Sync for AtomicQueue<T> {}
+
+impl<T> Default for AtomicQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> AtomicQueue<T> {
+    pub fn new() -> Self {
+        let dummy = QueueNode::new(MaybeUninit::uninit());
+        AtomicQueue {
+            head: AtomicPtr::new(dummy),
+            tail: AtomicPtr::new(dummy),
+            domain: Domain::new(),
+            _owns: PhantomData,
+        }
+    }
+
+    pub fn enqueue(&self, elem: T) {
+        let node = QueueNode::new(MaybeUninit::new(elem));
+        let hazard = self.domain.hazard();
+        loop {
+            let tail = hazard.protect(&self.tail);
+            // SAFETY: tail is protected and the tail is never null
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if !next.is_null() {
+                // the tail lags behind, help it forward and retry
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            // SAFETY: as above
+            let linked = unsafe {
+                (*tail).next.compare_exchange(
+                    ptr::null_mut(),
+                    node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+            };
+            if linked.is_ok() {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, node, Ordering::Release, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+
+    pub fn dequeue(&self) -> Option<T> {
+        let head_hazard = self.domain.hazard();
+        let next_hazard = self.domain.hazard();
+        loop {
+            let head = head_hazard.protect(&self.head);
+            // SAFETY: head is protected and never null
+            let next = next_hazard.protect(unsafe { &(*head).next });
+            // next is only safe if head was still the head when it was
+            // protected, otherwise it may have been dequeued and retired
+            if self.head.load(Ordering::Acquire) != head {
+                continue;
+            }
+            if next.is_null() {
+                return None;
+            }
+            let tail = self.tail.load(Ordering::Acquire);
+            if head == tail {
+                // never let the head pass the tail
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                // SAFETY: next is protected and is now the dummy; only the
+                // thread that moved the head onto it reads its item. The old
+                // dummy is unreachable from the head and tail.
+                unsafe {
+                    let elem = (*next).elem.assume_init_read();
+                    drop(head_hazard);
+                    drop(next_hazard);
+                    self.domain.retire(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    /// A snapshot, other threads may change it right after.
+    pub fn is_empty(&self) -> bool {
+        let hazard = self.domain.hazard();
+        let head = hazard.protect(&self.head);
+        // SAFETY: head is protected and never null
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+}
+
+impl<T> Drop for AtomicQueue<T> {
+    fn drop(&mut self) {
+        // SAFETY: &mut self; the dummy has no item, every node after it has
+        unsafe {
+            let dummy = Box::from_raw(*self.head.get_mut());
+            let mut node = dummy.next.load(Ordering::Relaxed);
+            while !node.is_null() {
+                let mut boxed = Box::from_raw(node);
+                boxed.elem.assume_init_drop();
+                node = boxed.next.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, sync::Arc, thread};
+
+    // miri is far too slow for the full stress runs
+    const PER_THREAD: usize = if cfg!(miri) { 50 } else { 20_000 };
+    const THREADS: usize = 4;
+
+    #[test]
+    fn stack_single_thread() {
+        let stack = AtomicStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        for i in 0..200 {
+            stack.push(i);
+        }
+        assert!(!stack.is_empty());
+        for i in (100..200).rev() {
+            assert_eq!(stack.pop(), Some(i));
+        }
+        stack.push(7);
+        assert_eq!(stack.pop(), Some(7));
+        assert_eq!(stack.pop(), Some(99));
+    }
+
+    #[test]
+    fn queue_single_thread() {
+        let queue = AtomicQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.dequeue(), None);
+        for i in 0..200 {
+            queue.enqueue(i);
+        }
+        assert!(!queue.is_empty());
+        for i in 0..100 {
+            assert_eq!(queue.dequeue(), Some(i));
+        }
+        queue.enqueue(7);
+        for i in 100..200 {
+            assert_eq!(queue.dequeue(), Some(i));
+        }
+        assert_eq!(queue.dequeue(), Some(7));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn drops_every_item_once() {
+        let item = Arc::new(());
+        {
+            let stack = AtomicStack::new();
+            let queue = AtomicQueue::new();
+            for _ in 0..100 {
+                stack.push(item.clone());
+                queue.enqueue(item.clone());
+            }
+            for _ in 0..70 {
+                drop(stack.pop());
+                drop(queue.dequeue());
+            }
+            assert_eq!(Arc::strong_count(&item), 61);
+        }
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    // Every producer pushes its own range of ids while consumers pop
+    // until all ids are seen. Each id must come out exactly once.
+    fn stress(
+        push: impl Fn(usize) + Sync,
+        pop: impl Fn() -> Option<usize> + Sync,
+    ) -> Vec<Vec<usize>> {
+        let total = THREADS * PER_THREAD;
+        let taken = AtomicUsize::new(0);
+        let seen = thread::scope(|s| {
+            for t in 0..THREADS {
+                let push = &push;
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        push(t * PER_THREAD + i);
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut seen = Vec::new();
+                        while taken.load(Ordering::Relaxed) < total {
+                            match pop() {
+                                Some(id) => {
+                                    taken.fetch_add(1, Ordering::Relaxed);
+                                    seen.push(id);
+                                }
+                                None => thread::yield_now(),
+                            }
+                        }
+                        seen
+                    })
+                })
+                .collect();
+            consumers
+                .into_iter()
+                .map(|c| c.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let all: HashSet<_> = seen.iter().flatten().copied().collect();
+        assert_eq!(
+            seen.iter().map(Vec::len).sum::<usize>(),
+            total,
+            "duplicated items"
+        );
+        assert_eq!(all.len(), total, "lost items");
+        assert!(all.iter().all(|id| *id < total));
+        seen
+    }
+
+    #[test]
+    fn stack_mpmc_stress() {
+        let stack = AtomicStack::new();
+        stress(|id| stack.push(id), || stack.pop());
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn queue_mpmc_stress() {
+        let queue = AtomicQueue::new();
+        let seen = stress(|id| queue.enqueue(id), || queue.dequeue());
+        assert!(queue.is_empty());
+        // a consumer sees the items of each producer in the order pushed
+        for ids in seen {
+            let mut last = [None; THREADS];
+            for id in ids {
+                let producer = id / PER_THREAD;
+                assert!(last[producer] < Some(id));
+                last[producer] = Some(id);
+            }
+        }
+    }
+}
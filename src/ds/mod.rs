@@ -2,3 +2,29 @@
 
 //pub mod trees_and_storage_v2;
 //pub use trees_and_storage_v2::*;
+pub mod array_list;
+pub use array_list::*;
+pub mod ring_buffer;
+pub use ring_buffer::*;
+pub mod gap_buffer;
+pub use gap_buffer::*;
+pub mod rope;
+pub use rope::*;
+pub mod sorted_array_list;
+pub use sorted_array_list::*;
+pub mod linked_list_draft;
+pub use linked_list_draft::*;
+pub mod dlist;
+pub use dlist::*;
+pub mod linked_list;
+pub use linked_list::*;
+pub mod stack;
+pub use stack::*;
+pub mod persistent_list;
+pub use persistent_list::*;
+pub mod lock_free;
+pub use lock_free::*;
+pub mod unrolled_list;
+pub use unrolled_list::*;
+pub mod cache;
+pub use cache::*;
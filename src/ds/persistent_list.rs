@@ -0,0 +1,281 @@
+use std::{
+    fmt::{self, Debug},
+    rc::Rc,
+    sync::Arc,
+};
+
+/*
+ * PersistentList
+ * Immutable singly linked list where every version shares its tail with
+ * the versions it was made from, so a "copy" is one pointer clone.
+ *
+ *   let a = [].prepend(3).prepend(2);    a -> [2] -> [3]
+ *   let b = a.prepend(1);                b -> [1] --^
+ *   let c = a.tail();                    c ---------^
+ *
+ * prepend, tail, head and clone are O(1) and never touch existing nodes.
+ * A node is freed once the last list that reaches it goes away. Drop
+ * walks the chain in a loop (stopping at the first node someone else
+ * still holds) instead of recursing, so long lists can not overflow the
+ * stack.
+ *
+ * PersistentList uses Rc, ArcPersistentList is the same list over Arc
+ * for sharing snapshots between threads.
+ * */
+
+macro_rules! persistent_list {
+    ($list:ident, $node:ident, $iter:ident, $ptr:ident) => {
+        struct $node<T> {
+            elem: T,
+            len: usize,
+            next: Option<$ptr<$node<T>>>,
+        }
+
+        pub struct $list<T> {
+            head: Option<$ptr<$node<T>>>,
+        }
+
+        impl<T> $list<T> {
+            pub fn new() -> Self {
+                $list { head: None }
+            }
+
+            /// New list with `elem` in front of this one, sharing it.
+            pub fn prepend(&self, elem: T) -> Self {
+                $list {
+                    head: Some($ptr::new($node {
+                        elem,
+                        len: self.len() + 1,
+                        next: self.head.clone(),
+                    })),
+                }
+            }
+
+            /// This list without its first item, sharing it. Empty for
+            /// an empty list.
+            pub fn tail(&self) -> Self {
+                $list {
+                    head: self.head.as_ref().and_then(|node| node.next.clone()),
+                }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.elem)
+            }
+
+            pub fn len(&self) -> usize {
+                self.head.as_ref().map_or(0, |node| node.len)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.head.is_none()
+            }
+
+            /// True if both lists are the same version, not just equal.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                match (&self.head, &other.head) {
+                    (Some(a), Some(b)) => $ptr::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+            }
+
+            pub fn iter(&self) -> $iter<'_, T> {
+                $iter {
+                    next: self.head.as_deref(),
+                }
+            }
+        }
+
+        impl<T> Default for $list<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T> Clone for $list<T> {
+            fn clone(&self) -> Self {
+                $list {
+                    head: self.head.clone(),
+                }
+            }
+        }
+
+        impl<T> Drop for $list<T> {
+            fn drop(&mut self) {
+                let mut head = self.head.take();
+                while let Some(node) = head {
+                    // into_inner, not try_unwrap: when two handles to a node
+                    // drop at once on different threads, exactly one of them
+                    // gets it back and keeps the loop going, instead of the
+                    // last Err(node) dropping the rest of the chain recursively
+                    match $ptr::into_inner(node) {
+                        Some(mut node) => head = node.next.take(),
+                        // someone else still uses the rest of the chain
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for $list<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.len() == other.len() && self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: Debug> Debug for $list<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        pub struct $iter<'a, T> {
+            next: Option<&'a $node<T>>,
+        }
+
+        impl<'a, T> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next.map(|node| {
+                    self.next = node.next.as_deref();
+                    &node.elem
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.next.map_or(0, |node| node.len);
+                (len, Some(len))
+            }
+        }
+
+        impl<T> ExactSizeIterator for $iter<'_, T> {}
+    };
+}
+
+persistent_list!(PersistentList, RcNode, PersistentIter, Rc);
+persistent_list!(ArcPersistentList, ArcNode, ArcPersistentIter, Arc);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Barrier, thread};
+
+    #[test]
+    fn prepend_and_tail_share() {
+        let empty = PersistentList::new();
+        let a = empty.prepend(3).prepend(2);
+        let b = a.prepend(1);
+        let c = a.tail();
+
+        assert_eq!(b.iter().collect::<Vec<_>>(), [&1, &2, &3]);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [&2, &3]);
+        assert_eq!(c.iter().collect::<Vec<_>>(), [&3]);
+        assert_eq!(b.len(), 3);
+        assert_eq!(b.head(), Some(&1));
+        assert!(b.tail().ptr_eq(&a));
+        assert!(a.tail().ptr_eq(&c));
+        assert!(!a.ptr_eq(&a.tail().prepend(2)));
+        assert_eq!(a, a.tail().prepend(2));
+
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), None);
+        assert!(empty.tail().is_empty());
+        assert!(c.tail().tail().ptr_eq(&empty));
+    }
+
+    #[test]
+    fn old_versions_survive() {
+        // undo history: every edit is a new version, older ones stay valid
+        let mut history = vec![PersistentList::new()];
+        for word in ["a", "b", "c"] {
+            let next = history.last().unwrap().prepend(word);
+            history.push(next);
+        }
+        let undone = history[3].tail();
+        assert!(undone.ptr_eq(&history[2]));
+        assert_eq!(format!("{:?}", history[3]), r#"["c", "b", "a"]"#);
+        assert_eq!(format!("{:?}", history[1]), r#"["a"]"#);
+        drop(history.pop());
+        assert_eq!(undone.len(), 2);
+    }
+
+    #[test]
+    fn frees_unshared_nodes_only() {
+        let item = Rc::new(());
+        let base = PersistentList::new()
+            .prepend(item.clone())
+            .prepend(item.clone());
+        let longer = base.prepend(item.clone());
+        assert_eq!(Rc::strong_count(&item), 4);
+        drop(longer);
+        assert_eq!(Rc::strong_count(&item), 3);
+        let tail = base.tail();
+        drop(base);
+        assert_eq!(Rc::strong_count(&item), 2);
+        drop(tail);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn long_lists_drop_without_recursion() {
+        let n = if cfg!(miri) { 1_000 } else { 200_000 };
+        let mut list = PersistentList::new();
+        for i in 0..n {
+            list = list.prepend(i);
+        }
+        let shared = list.tail().tail();
+        drop(list);
+        assert_eq!(shared.len(), n - 2);
+        drop(shared);
+
+        let mut list = ArcPersistentList::new();
+        for i in 0..n {
+            list = list.prepend(i);
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn arc_lists_dropped_on_two_threads_at_once() {
+        let n = if cfg!(miri) { 1_000 } else { 200_000 };
+        let rounds = if cfg!(miri) { 2 } else { 20 };
+        for _ in 0..rounds {
+            let list = (0..n).fold(ArcPersistentList::new(), |l, i| l.prepend(i));
+            let barrier = Arc::new(Barrier::new(2));
+            let handles: Vec<_> = [list.clone(), list]
+                .into_iter()
+                .map(|list| {
+                    let barrier = barrier.clone();
+                    thread::spawn(move || {
+                        barrier.wait();
+                        drop(list);
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn arc_snapshots_across_threads() {
+        let base: ArcPersistentList<_> =
+            (0..10).fold(ArcPersistentList::new(), |l, i| l.prepend(i));
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let snapshot = base.clone();
+                thread::spawn(move || {
+                    let mine = snapshot.prepend(100 + t);
+                    assert!(mine.tail().ptr_eq(&snapshot));
+                    mine.iter().sum::<i32>()
+                })
+            })
+            .collect();
+        let sums: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(sums, [145, 146, 147, 148]);
+        assert_eq!(base.len(), 10);
+        assert_eq!(base.iter().len(), 10);
+    }
+}
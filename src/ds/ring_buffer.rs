@@ -0,0 +1,424 @@
+use std::{
+    fmt::{self, Debug},
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+    ptr, slice,
+};
+
+use crate::growth::{Doubling, GrowthPolicy};
+
+/*
+ * RingBuffer (ArrayDeque)
+ * A fixed block of slots where the items start at `head` and wrap
+ * around the end, so both ends can grow and shrink without shifting.
+ *
+ *   capacity = 8, head = 6, len = 4
+ *   index     0   1   2   3   4   5   6   7
+ *   slots   [ c | d | ? | ? | ? | ? | a | b ]
+ *                                    ^head
+ *   logical order: a b c d, as_slices() = ([a, b], [c, d])
+ *
+ * push_front/push_back/pop_front/pop_back/get are O(1).
+ * When full it grows like Doubling and copies the two halves into the
+ * new block in order, so the ring starts unwrapped at 0 again.
+ *
+ * Bounded mode never grows: pushing into a full buffer overwrites the
+ * item at the other end, which keeps the last N samples of a stream.
+ * */
+
+pub struct RingBuffer<T> {
+    buf: Box<[MaybeUninit<T>]>,
+    head: usize,
+    len: usize,
+    bounded: bool,
+}
+
+pub type ArrayDeque<T> = RingBuffer<T>;
+
+impl<T> Default for RingBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        RingBuffer {
+            buf: Box::new_uninit_slice(capacity),
+            head: 0,
+            len: 0,
+            bounded: false,
+        }
+    }
+
+    /// Buffer that holds at most `capacity` items, a push into a full
+    /// buffer drops the item at the opposite end.
+    pub fn bounded(capacity: usize) -> Self {
+        let mut ring = Self::with_capacity(capacity);
+        ring.bounded = true;
+        ring
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.bounded
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    // slot of the logical index, without overflowing head + index
+    fn physical(&self, index: usize) -> usize {
+        let to_end = self.capacity() - self.head;
+        if index >= to_end {
+            index - to_end
+        } else {
+            self.head + index
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .len
+            .checked_add(additional)
+            .expect("RingBuffer capacity overflow");
+        if required > self.capacity() {
+            self.grow_to(Doubling.grow(self.capacity(), required));
+        }
+    }
+
+    // moves the items into a block of new_cap slots, starting at 0
+    fn grow_to(&mut self, new_cap: usize) {
+        let mut buf = Box::new_uninit_slice(new_cap);
+        let (front, back) = self.as_slices();
+        // SAFETY: the new block has room for len items, both halves are
+        // initialized and the old block is freed without dropping them
+        unsafe {
+            let dst = buf.as_mut_ptr() as *mut T;
+            ptr::copy_nonoverlapping(front.as_ptr(), dst, front.len());
+            ptr::copy_nonoverlapping(back.as_ptr(), dst.add(front.len()), back.len());
+        }
+        self.buf = buf;
+        self.head = 0;
+    }
+
+    pub fn push_back(&mut self, item: T) {
+        if self.is_full() {
+            if self.bounded {
+                if self.capacity() == 0 {
+                    return;
+                }
+                drop(self.pop_front());
+            } else {
+                self.reserve(1);
+            }
+        }
+        let slot = self.physical(self.len);
+        self.buf[slot].write(item);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, item: T) {
+        if self.is_full() {
+            if self.bounded {
+                if self.capacity() == 0 {
+                    return;
+                }
+                drop(self.pop_back());
+            } else {
+                self.reserve(1);
+            }
+        }
+        self.head = match self.head {
+            0 => self.capacity() - 1,
+            head => head - 1,
+        };
+        self.buf[self.head].write(item);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        // SAFETY: head holds the first item, moving head past it makes
+        // sure it is not read or dropped again
+        let item = unsafe { self.buf[self.head].assume_init_read() };
+        self.head = self.physical(1);
+        self.len -= 1;
+        Some(item)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        let slot = self.physical(self.len);
+        // SAFETY: the slot held the last item and is now past len
+        Some(unsafe { self.buf[slot].assume_init_read() })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        // SAFETY: logical indexes below len map to initialized slots
+        Some(unsafe { self.buf[self.physical(index)].assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let slot = self.physical(index);
+        // SAFETY: logical indexes below len map to initialized slots
+        Some(unsafe { self.buf[slot].assume_init_mut() })
+    }
+
+    /// The items in order as two slices, the second one is empty unless
+    /// the ring wraps around the end of the block.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let first = self.len.min(self.capacity() - self.head);
+        // SAFETY: [head, head + first) and [0, len - first) are the
+        // initialized slots
+        unsafe {
+            let base = self.buf.as_ptr() as *const T;
+            (
+                slice::from_raw_parts(base.add(self.head), first),
+                slice::from_raw_parts(base, self.len - first),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let first = self.len.min(self.capacity() - self.head);
+        // SAFETY: same slots as as_slices, and the two ranges never overlap
+        unsafe {
+            let base = self.buf.as_mut_ptr() as *mut T;
+            (
+                slice::from_raw_parts_mut(base.add(self.head), first),
+                slice::from_raw_parts_mut(base, self.len - first),
+            )
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (front, back) = self.as_slices();
+        front.iter().chain(back)
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+        self.head = 0;
+    }
+}
+
+impl<T> Index<usize> for RingBuffer<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for length {len}"))
+    }
+}
+
+impl<T> IndexMut<usize> for RingBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for length {len}"))
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        // SAFETY: both halves are initialized and never used again
+        unsafe {
+            ptr::drop_in_place(front);
+            ptr::drop_in_place(back);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for RingBuffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ring = RingBuffer::new();
+        for item in iter {
+            ring.push_back(item);
+        }
+        ring
+    }
+}
+
+impl<T: Debug> Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::{collections::VecDeque, rc::Rc};
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut ring = RingBuffer::new();
+        assert_eq!(ring.pop_front(), None);
+        assert_eq!(ring.pop_back(), None);
+        ring.push_back(2);
+        ring.push_back(3);
+        ring.push_front(1);
+        ring.push_front(0);
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.front(), Some(&0));
+        assert_eq!(ring.back(), Some(&3));
+        assert_eq!(ring.pop_front(), Some(0));
+        assert_eq!(ring.pop_back(), Some(3));
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut ring = RingBuffer::with_capacity(4);
+        ring.push_back('a');
+        ring.push_back('b');
+        ring.pop_front();
+        ring.pop_front();
+        // head is at 2 now, the next four pushes wrap
+        for c in ['c', 'd', 'e', 'f'] {
+            ring.push_back(c);
+        }
+        assert_eq!(ring.capacity(), 4);
+        assert_eq!(ring.as_slices(), (&['c', 'd'][..], &['e', 'f'][..]));
+        assert_eq!(ring[0], 'c');
+        assert_eq!(ring[3], 'f');
+        assert_eq!(ring.get(4), None);
+        ring[2] = 'E';
+        assert_eq!(ring.get(2), Some(&'E'));
+    }
+
+    #[test]
+    fn growth_unwraps() {
+        let mut ring = RingBuffer::with_capacity(4);
+        ring.push_back(2);
+        ring.push_back(3);
+        ring.push_front(1);
+        ring.push_front(0);
+        let (front, back) = ring.as_slices();
+        assert_eq!((front, back), (&[0, 1][..], &[2, 3][..]));
+
+        ring.push_back(4);
+        assert_eq!(ring.capacity(), 8);
+        assert_eq!(ring.as_slices(), (&[0, 1, 2, 3, 4][..], &[][..]));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds for length 2")]
+    fn index_out_of_bounds() {
+        let ring: RingBuffer<_> = [1, 2].into_iter().collect();
+        let _ = ring[2];
+    }
+
+    #[test]
+    fn bounded_overwrites_oldest() {
+        let mut window = RingBuffer::bounded(3);
+        for i in 0..10 {
+            window.push_back(i);
+        }
+        assert!(window.is_full());
+        assert_eq!(window.capacity(), 3);
+        assert_eq!(window.iter().copied().collect::<Vec<_>>(), vec![7, 8, 9]);
+
+        window.push_front(6);
+        assert_eq!(window.iter().copied().collect::<Vec<_>>(), vec![6, 7, 8]);
+
+        let mut empty = RingBuffer::bounded(0);
+        empty.push_back(1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn drops_items() {
+        let item = Rc::new(());
+        let mut ring = RingBuffer::with_capacity(4);
+        for _ in 0..3 {
+            ring.push_back(item.clone());
+        }
+        for _ in 0..3 {
+            ring.push_front(item.clone());
+        }
+        drop(ring.pop_back());
+        assert_eq!(Rc::strong_count(&item), 6);
+
+        let mut window = RingBuffer::bounded(2);
+        for _ in 0..5 {
+            window.push_back(item.clone());
+        }
+        assert_eq!(Rc::strong_count(&item), 8);
+        drop(ring);
+        drop(window);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn zst() {
+        let mut ring = RingBuffer::new();
+        for _ in 0..100 {
+            ring.push_front(());
+        }
+        assert_eq!(ring.len(), 100);
+        assert_eq!(ring.pop_back(), Some(()));
+    }
+
+    #[test]
+    fn matches_vec_deque() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut ring = RingBuffer::new();
+        let mut model = VecDeque::new();
+        for i in 0..5000 {
+            match rng.gen_range(0, 4) {
+                0 => {
+                    ring.push_back(i);
+                    model.push_back(i);
+                }
+                1 => {
+                    ring.push_front(i);
+                    model.push_front(i);
+                }
+                2 => assert_eq!(ring.pop_back(), model.pop_back()),
+                _ => assert_eq!(ring.pop_front(), model.pop_front()),
+            }
+            assert_eq!(ring.len(), model.len());
+            let (a, b) = ring.as_slices();
+            assert_eq!([a, b].concat(), model.iter().copied().collect::<Vec<_>>());
+        }
+    }
+}
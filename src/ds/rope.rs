@@ -0,0 +1,573 @@
+use std::{
+    fmt::{self, Debug, Display},
+    mem,
+    ops::Range,
+};
+
+/*
+ * Rope
+ * A string stored as a balanced binary tree of small chunks. Every node
+ * caches how many chars and newlines are below it, so positions and line
+ * numbers are found by walking down one path instead of scanning.
+ *
+ *   "hello world\n" as three chunks, nodes show (chars, newlines)
+ *
+ *                  (12, 1)
+ *                 /       \
+ *      "hello " (6, 0)   (6, 1)
+ *                       /      \
+ *              "wor" (3, 0)   "ld\n" (3, 1)
+ *
+ * Everything is built from two operations:
+ *   split(pos)   cuts the tree in two along one root-to-leaf path
+ *   join(a, b)   glues two trees, hanging the shorter one off the side
+ *                of the taller one and rotating on the way back up
+ *                (AVL style), so heights stay O(log n)
+ *
+ *   insert(pos, s)   split at pos, join(left, s, right)
+ *   delete(a..b)     split at a and b, join the outer parts
+ *
+ * char_at, char_to_line and line_to_char walk one path, O(log n).
+ * slice and chunk iteration are O(log n + k) for k chars.
+ * Positions are char indexes, not byte offsets.
+ * */
+
+// leaves hold at most this many bytes, adjacent small leaves are merged
+// when they get joined
+const MAX_CHUNK: usize = 512;
+
+struct Node {
+    chars: usize,
+    newlines: usize,
+    height: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Leaf(String),
+    Branch(Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn leaf(text: String) -> Node {
+        Node {
+            chars: text.chars().count(),
+            newlines: text.bytes().filter(|b| *b == b'\n').count(),
+            height: 0,
+            kind: Kind::Leaf(text),
+        }
+    }
+
+    fn empty() -> Node {
+        Node::leaf(String::new())
+    }
+
+    fn branch(left: Node, right: Node) -> Node {
+        Node {
+            chars: left.chars + right.chars,
+            newlines: left.newlines + right.newlines,
+            height: 1 + left.height.max(right.height),
+            kind: Kind::Branch(Box::new(left), Box::new(right)),
+        }
+    }
+}
+
+fn build(text: &str) -> Node {
+    let mut leaves = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_CHUNK);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        leaves.push(Node::leaf(rest[..end].to_string()));
+        rest = &rest[end..];
+    }
+    from_leaves(leaves)
+}
+
+// perfectly balanced tree over the leaves, in order
+fn from_leaves(mut leaves: Vec<Node>) -> Node {
+    match leaves.len() {
+        0 => Node::empty(),
+        1 => leaves.pop().unwrap(),
+        len => {
+            let right = leaves.split_off(len / 2);
+            Node::branch(from_leaves(leaves), from_leaves(right))
+        }
+    }
+}
+
+fn join(left: Node, right: Node) -> Node {
+    if left.chars == 0 {
+        return right;
+    }
+    if right.chars == 0 {
+        return left;
+    }
+    if left.height > right.height + 1 {
+        let Kind::Branch(ll, lr) = left.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        return balance(*ll, join(*lr, right));
+    }
+    if right.height > left.height + 1 {
+        let Kind::Branch(rl, rr) = right.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        return balance(join(left, *rl), *rr);
+    }
+    if let (Kind::Leaf(a), Kind::Leaf(b)) = (&left.kind, &right.kind)
+        && a.len() + b.len() <= MAX_CHUNK
+    {
+        return Node::leaf(format!("{a}{b}"));
+    }
+    Node::branch(left, right)
+}
+
+// branch over two subtrees whose heights differ by at most 2, rotating
+// once or twice when they differ by 2
+fn balance(left: Node, right: Node) -> Node {
+    if left.height > right.height + 1 {
+        let Kind::Branch(ll, lr) = left.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        if ll.height >= lr.height {
+            return Node::branch(*ll, Node::branch(*lr, right));
+        }
+        let Kind::Branch(lrl, lrr) = lr.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        return Node::branch(Node::branch(*ll, *lrl), Node::branch(*lrr, right));
+    }
+    if right.height > left.height + 1 {
+        let Kind::Branch(rl, rr) = right.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        if rr.height >= rl.height {
+            return Node::branch(Node::branch(left, *rl), *rr);
+        }
+        let Kind::Branch(rll, rlr) = rl.kind else {
+            unreachable!("a node taller than a leaf is a branch")
+        };
+        return Node::branch(Node::branch(left, *rll), Node::branch(*rlr, *rr));
+    }
+    Node::branch(left, right)
+}
+
+// first `pos` chars on the left, the rest on the right
+fn split(node: Node, pos: usize) -> (Node, Node) {
+    match node.kind {
+        Kind::Leaf(mut text) => {
+            let byte = text.char_indices().nth(pos).map_or(text.len(), |(i, _)| i);
+            let rest = text.split_off(byte);
+            (Node::leaf(text), Node::leaf(rest))
+        }
+        Kind::Branch(left, right) => {
+            if pos == left.chars {
+                (*left, *right)
+            } else if pos < left.chars {
+                let (a, b) = split(*left, pos);
+                (a, join(b, *right))
+            } else {
+                let (a, b) = split(*right, pos - left.chars);
+                (join(*left, a), b)
+            }
+        }
+    }
+}
+
+fn collect(node: &Node, start: usize, end: usize, out: &mut String) {
+    if start >= end {
+        return;
+    }
+    match &node.kind {
+        Kind::Leaf(text) => out.extend(text.chars().skip(start).take(end - start)),
+        Kind::Branch(left, right) => {
+            if start < left.chars {
+                collect(left, start, end.min(left.chars), out);
+            }
+            if end > left.chars {
+                collect(
+                    right,
+                    start.saturating_sub(left.chars),
+                    end - left.chars,
+                    out,
+                );
+            }
+        }
+    }
+}
+
+// char index of the nth (zero based) newline, n < node.newlines
+fn nth_newline(node: &Node, n: usize) -> usize {
+    match &node.kind {
+        Kind::Leaf(text) => text
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '\n')
+            .nth(n)
+            .map(|(i, _)| i)
+            .expect("newline count is out of sync"),
+        Kind::Branch(left, right) => {
+            if n < left.newlines {
+                nth_newline(left, n)
+            } else {
+                left.chars + nth_newline(right, n - left.newlines)
+            }
+        }
+    }
+}
+
+fn newlines_before(node: &Node, pos: usize) -> usize {
+    match &node.kind {
+        Kind::Leaf(text) => text.chars().take(pos).filter(|c| *c == '\n').count(),
+        Kind::Branch(left, right) => {
+            if pos <= left.chars {
+                newlines_before(left, pos)
+            } else {
+                left.newlines + newlines_before(right, pos - left.chars)
+            }
+        }
+    }
+}
+
+pub struct Rope {
+    root: Node,
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Rope {
+            root: Node::empty(),
+        }
+    }
+
+    /// Length in chars.
+    pub fn len(&self) -> usize {
+        self.root.chars
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.root.newlines + 1
+    }
+
+    /// # Panics
+    ///
+    /// If `pos > len`.
+    pub fn insert(&mut self, pos: usize, text: &str) {
+        let len = self.len();
+        assert!(pos <= len, "position {pos} out of bounds for length {len}");
+        if text.is_empty() {
+            return;
+        }
+        let root = mem::replace(&mut self.root, Node::empty());
+        let (left, right) = split(root, pos);
+        self.root = join(join(left, build(text)), right);
+    }
+
+    /// # Panics
+    ///
+    /// If the range is reversed or ends past `len`.
+    pub fn delete(&mut self, range: Range<usize>) {
+        self.check_range(&range);
+        let root = mem::replace(&mut self.root, Node::empty());
+        let (left, rest) = split(root, range.start);
+        let (_, right) = split(rest, range.end - range.start);
+        self.root = join(left, right);
+    }
+
+    /// # Panics
+    ///
+    /// If the range is reversed or ends past `len`.
+    pub fn slice(&self, range: Range<usize>) -> String {
+        self.check_range(&range);
+        let mut out = String::new();
+        collect(&self.root, range.start, range.end, &mut out);
+        out
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        let len = self.len();
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "range {range:?} out of bounds for length {len}"
+        );
+    }
+
+    pub fn char_at(&self, pos: usize) -> Option<char> {
+        if pos >= self.len() {
+            return None;
+        }
+        let mut node = &self.root;
+        let mut pos = pos;
+        loop {
+            match &node.kind {
+                Kind::Leaf(text) => return text.chars().nth(pos),
+                Kind::Branch(left, right) => {
+                    if pos < left.chars {
+                        node = left;
+                    } else {
+                        pos -= left.chars;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Zero based line the char at `pos` is on.
+    ///
+    /// # Panics
+    ///
+    /// If `pos > len`.
+    pub fn char_to_line(&self, pos: usize) -> usize {
+        let len = self.len();
+        assert!(pos <= len, "position {pos} out of bounds for length {len}");
+        newlines_before(&self.root, pos)
+    }
+
+    /// Position of the first char of `line`, None past the last line.
+    pub fn line_to_char(&self, line: usize) -> Option<usize> {
+        match line {
+            0 => Some(0),
+            line if line > self.root.newlines => None,
+            line => Some(nth_newline(&self.root, line - 1) + 1),
+        }
+    }
+
+    /// Text of `line` without its trailing newline.
+    pub fn line(&self, line: usize) -> Option<String> {
+        let start = self.line_to_char(line)?;
+        let end = self
+            .line_to_char(line + 1)
+            .map_or(self.len(), |next| next - 1);
+        Some(self.slice(start..end))
+    }
+
+    pub fn chunks(&self) -> RopeChunks<'_> {
+        RopeChunks {
+            stack: vec![&self.root],
+        }
+    }
+
+    /// Rebuilds a perfectly balanced tree, packing the text into as few
+    /// chunks as possible. Useful after many small deletes left a lot of
+    /// tiny leaves behind.
+    pub fn rebalance(&mut self) {
+        self.root = build(&self.to_string());
+    }
+}
+
+/// The text of a [`Rope`] chunk by chunk, in order.
+pub struct RopeChunks<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for RopeChunks<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            match &node.kind {
+                Kind::Leaf(text) if text.is_empty() => {}
+                Kind::Leaf(text) => return Some(text),
+                Kind::Branch(left, right) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Rope { root: build(text) }
+    }
+}
+
+impl Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.to_string(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn leaves(node: &Node) -> usize {
+        match &node.kind {
+            Kind::Leaf(_) => 1,
+            Kind::Branch(left, right) => leaves(left) + leaves(right),
+        }
+    }
+
+    // AVL bound: height <= 1.44 log2(leaves + 2)
+    fn assert_balanced(rope: &Rope) {
+        let n = leaves(&rope.root) as f64;
+        let bound = (1.45 * (n + 2.0).log2()).ceil() as usize;
+        assert!(
+            rope.root.height <= bound,
+            "height {} for {n} leaves",
+            rope.root.height
+        );
+    }
+
+    #[test]
+    fn insert_delete_slice() {
+        let mut rope = Rope::from("hello world");
+        rope.insert(5, ",");
+        rope.insert(rope.len(), "!");
+        assert_eq!(rope.to_string(), "hello, world!");
+        rope.delete(0..1);
+        rope.insert(0, "H");
+        assert_eq!(rope.slice(0..5), "Hello");
+        assert_eq!(rope.slice(7..12), "world");
+        assert_eq!(rope.char_at(4), Some('o'));
+        assert_eq!(rope.char_at(13), None);
+        rope.delete(0..rope.len());
+        assert!(rope.is_empty());
+        assert_eq!(format!("{rope:?}"), "\"\"");
+    }
+
+    #[test]
+    fn char_positions_not_bytes() {
+        let mut rope = Rope::from("añb€c");
+        assert_eq!(rope.len(), 5);
+        assert_eq!(rope.char_at(3), Some('€'));
+        rope.insert(2, "ü");
+        assert_eq!(rope.slice(1..4), "ñüb");
+        rope.delete(4..5);
+        assert_eq!(rope.to_string(), "añübc");
+    }
+
+    #[test]
+    #[should_panic(expected = "range 3..9 out of bounds for length 5")]
+    fn delete_out_of_bounds() {
+        Rope::from("hello").delete(3..9);
+    }
+
+    #[test]
+    fn lines() {
+        let rope = Rope::from("first\nsecond\n\nlast");
+        assert_eq!(rope.line_count(), 4);
+        assert_eq!(rope.line_to_char(1), Some(6));
+        assert_eq!(rope.line_to_char(3), Some(14));
+        assert_eq!(rope.line_to_char(4), None);
+        assert_eq!(rope.char_to_line(0), 0);
+        assert_eq!(rope.char_to_line(5), 0);
+        assert_eq!(rope.char_to_line(6), 1);
+        assert_eq!(rope.char_to_line(rope.len()), 3);
+        assert_eq!(rope.line(1).as_deref(), Some("second"));
+        assert_eq!(rope.line(2).as_deref(), Some(""));
+        assert_eq!(rope.line(3).as_deref(), Some("last"));
+        assert_eq!(Rope::from("a\n").line(1).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn large_text_spans_many_chunks() {
+        let text: String = (0..2000).map(|i| format!("line {i}\n")).collect();
+        let rope = Rope::from(text.as_str());
+        assert!(rope.chunks().count() > 10);
+        assert!(rope.chunks().all(|c| c.len() <= MAX_CHUNK));
+        assert_eq!(rope.chunks().collect::<String>(), text);
+        assert_eq!(rope.line_count(), 2001);
+        assert_eq!(rope.line(1234).as_deref(), Some("line 1234"));
+        let pos = rope.line_to_char(1500).unwrap();
+        assert_eq!(rope.char_to_line(pos), 1500);
+        assert_balanced(&rope);
+    }
+
+    #[test]
+    fn stays_balanced_under_edits() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut rope = Rope::new();
+        let chunk = "x".repeat(MAX_CHUNK);
+        for _ in 0..500 {
+            let pos = rng.gen_range(0, rope.len() + 1);
+            rope.insert(pos, &chunk);
+        }
+        assert_balanced(&rope);
+        // always appending is the worst case for a naive tree
+        let mut rope = Rope::new();
+        for _ in 0..500 {
+            rope.insert(rope.len(), &chunk);
+        }
+        assert_balanced(&rope);
+    }
+
+    #[test]
+    fn rebalance_packs_chunks() {
+        let text = "abcdefgh".repeat(1000);
+        let mut rope = Rope::from(text.as_str());
+        for i in 0..500 {
+            rope.delete(i * 7..i * 7 + 1);
+        }
+        let before = rope.to_string();
+        rope.rebalance();
+        assert_eq!(rope.to_string(), before);
+        assert_eq!(rope.chunks().count(), before.len().div_ceil(MAX_CHUNK));
+        assert_balanced(&rope);
+    }
+
+    #[test]
+    fn matches_string() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let alphabet: Vec<char> = "ab\nñ€ ".chars().collect();
+        let mut rope = Rope::new();
+        let mut model: Vec<char> = Vec::new();
+        for _ in 0..1000 {
+            let len = model.len();
+            let a = rng.gen_range(0, len + 1);
+            let b = rng.gen_range(0, len + 1);
+            let (start, end) = (a.min(b), a.max(b));
+            match rng.gen_range(0, 4) {
+                0 | 1 => {
+                    let n = rng.gen_range(0, 200);
+                    let text: String = (0..n)
+                        .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+                        .collect();
+                    rope.insert(a, &text);
+                    model.splice(a..a, text.chars());
+                }
+                2 => {
+                    rope.delete(start..end);
+                    model.drain(start..end);
+                }
+                _ => {
+                    let expected: String = model[start..end].iter().collect();
+                    assert_eq!(rope.slice(start..end), expected);
+                }
+            }
+            assert_eq!(rope.len(), model.len());
+            let pos = rng.gen_range(0, model.len() + 1);
+            assert_eq!(rope.char_at(pos), model.get(pos).copied());
+            let line = model[..pos].iter().filter(|c| **c == '\n').count();
+            assert_eq!(rope.char_to_line(pos), line);
+        }
+        assert_eq!(rope.to_string(), model.iter().collect::<String>());
+        assert_balanced(&rope);
+    }
+}
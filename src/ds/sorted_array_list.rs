@@ -0,0 +1,277 @@
+use std::{
+    fmt::{self, Debug},
+    ops::{Bound, RangeBounds},
+};
+
+use super::ArrayList;
+use crate::mergesort::zero_copy_merge;
+
+/*
+ * SortedArrayList
+ * An ArrayList that is always sorted, so lookups are binary searches.
+ *
+ *   items  [ 1 | 3 | 3 | 7 | 9 ]
+ *   rank(3) = 1 (items < 3), rank(8) = 4
+ *   range(3..8) = [3, 3, 7]
+ *
+ * contains/rank/range are O(log n), insert/remove are O(log n) to find
+ * the slot plus O(n) to shift the tail. For small sets that are read a
+ * lot more than written this beats a tree: one block, no pointers.
+ * Duplicates are kept (equal items stay in insertion order) until
+ * dedup is called.
+ * */
+
+pub struct SortedArrayList<T: Ord> {
+    items: ArrayList<T>,
+}
+
+impl<T: Ord> Default for SortedArrayList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// empties the list into a Vec, keeping the order
+fn take_vec<T>(list: &mut ArrayList<T>) -> Vec<T> {
+    let mut items = Vec::with_capacity(list.len());
+    while let Some(item) = list.pop() {
+        items.push(item);
+    }
+    items.reverse();
+    items
+}
+
+impl<T: Ord> SortedArrayList<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        SortedArrayList {
+            items: ArrayList::new(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.items.as_slice()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.items.get(idx)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.as_slice().last()
+    }
+
+    /// Inserts after any equal items and returns the index it landed on.
+    pub fn insert(&mut self, item: T) -> usize {
+        let idx = self.as_slice().partition_point(|x| *x <= item);
+        self.items.insert_at(item, idx);
+        idx
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.as_slice().binary_search(item).is_ok()
+    }
+
+    /// Number of items strictly less than `item`.
+    pub fn rank(&self, item: &T) -> usize {
+        self.as_slice().partition_point(|x| x < item)
+    }
+
+    /// Items inside `range`, as one contiguous slice.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> &[T] {
+        let items = self.as_slice();
+        let start = match range.start_bound() {
+            Bound::Included(v) => items.partition_point(|x| x < v),
+            Bound::Excluded(v) => items.partition_point(|x| x <= v),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(v) => items.partition_point(|x| x <= v),
+            Bound::Excluded(v) => items.partition_point(|x| x < v),
+            Bound::Unbounded => items.len(),
+        };
+        &items[start..end.max(start)]
+    }
+
+    /// Removes the first item equal to `item`.
+    pub fn remove(&mut self, item: &T) -> Option<T> {
+        let idx = self.rank(item);
+        if self.get(idx)? != item {
+            return None;
+        }
+        self.items.remove_at(idx).ok()
+    }
+
+    /// Moves every item of `other` in, keeping the order. On ties items
+    /// already in `self` come first.
+    pub fn merge(&mut self, mut other: SortedArrayList<T>) {
+        let ours = take_vec(&mut self.items);
+        let theirs = take_vec(&mut other.items);
+        for item in zero_copy_merge(ours, theirs) {
+            self.items.append(item);
+        }
+    }
+
+    /// Keeps only the first of each run of equal items.
+    pub fn dedup(&mut self) {
+        let mut items = take_vec(&mut self.items);
+        items.dedup();
+        for item in items {
+            self.items.append(item);
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedArrayList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items: Vec<T> = iter.into_iter().collect();
+        items.sort();
+        let mut list = SortedArrayList::with_capacity(items.len());
+        for item in items {
+            list.items.append(item);
+        }
+        list
+    }
+}
+
+impl<T: Ord + Debug> Debug for SortedArrayList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn insert_keeps_order() {
+        let mut list = SortedArrayList::new();
+        for x in [5, 1, 4, 1, 9, 2] {
+            list.insert(x);
+        }
+        assert_eq!(list.as_slice(), &[1, 1, 2, 4, 5, 9]);
+        assert_eq!(list.insert(3), 3);
+        assert_eq!(list.insert(1), 2);
+        assert_eq!(list.first(), Some(&1));
+        assert_eq!(list.last(), Some(&9));
+    }
+
+    #[test]
+    fn equal_items_keep_insertion_order() {
+        #[derive(Debug)]
+        struct Tagged(u32, &'static str);
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Tagged {}
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut list = SortedArrayList::new();
+        list.insert(Tagged(1, "a"));
+        list.insert(Tagged(0, "b"));
+        list.insert(Tagged(1, "c"));
+        let mut other = SortedArrayList::new();
+        other.insert(Tagged(1, "d"));
+        list.merge(other);
+        let tags: Vec<_> = list.iter().map(|t| t.1).collect();
+        assert_eq!(tags, ["b", "a", "c", "d"]);
+        assert_eq!(list.remove(&Tagged(1, "")).map(|t| t.1), Some("a"));
+    }
+
+    #[test]
+    fn lookups() {
+        let list: SortedArrayList<_> = [9, 3, 7, 1, 3].into_iter().collect();
+        assert!(list.contains(&7));
+        assert!(!list.contains(&8));
+        assert_eq!(list.rank(&0), 0);
+        assert_eq!(list.rank(&3), 1);
+        assert_eq!(list.rank(&8), 4);
+        assert_eq!(list.rank(&10), 5);
+        assert_eq!(list.range(3..8), &[3, 3, 7]);
+        assert_eq!(list.range(3..=9), &[3, 3, 7, 9]);
+        assert_eq!(list.range(..3), &[1]);
+        assert_eq!(list.range(4..), &[7, 9]);
+        assert_eq!(list.range((Bound::Excluded(3), Bound::Unbounded)), &[7, 9]);
+        assert!(list.range((Bound::Included(8), Bound::Excluded(4))).is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let mut list: SortedArrayList<_> = ["b", "a", "c", "b"].into_iter().collect();
+        assert_eq!(list.remove(&"b"), Some("b"));
+        assert_eq!(list.remove(&"z"), None);
+        assert_eq!(list.remove(&"0"), None);
+        assert_eq!(list.as_slice(), &["a", "b", "c"]);
+    }
+
+    #[test]
+    fn merge_and_dedup() {
+        let mut a: SortedArrayList<_> = [1, 4, 4, 8].into_iter().collect();
+        let b: SortedArrayList<_> = [0, 4, 5, 9, 9].into_iter().collect();
+        a.merge(b);
+        assert_eq!(a.as_slice(), &[0, 1, 4, 4, 4, 5, 8, 9, 9]);
+        a.dedup();
+        assert_eq!(a.as_slice(), &[0, 1, 4, 5, 8, 9]);
+        a.merge(SortedArrayList::new());
+        assert_eq!(a.len(), 6);
+        assert_eq!(format!("{a:?}"), "[0, 1, 4, 5, 8, 9]");
+    }
+
+    #[test]
+    fn matches_sorted_vec() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut list = SortedArrayList::new();
+        let mut model: Vec<u8> = Vec::new();
+        for _ in 0..2000 {
+            let x: u8 = rng.gen_range(0, 64);
+            match rng.gen_range(0, 3) {
+                0 => {
+                    list.insert(x);
+                    model.push(x);
+                    model.sort();
+                }
+                1 => {
+                    let expected = model.iter().position(|y| *y == x).map(|i| model.remove(i));
+                    assert_eq!(list.remove(&x), expected);
+                }
+                _ => {
+                    assert_eq!(list.contains(&x), model.contains(&x));
+                    assert_eq!(list.rank(&x), model.iter().filter(|y| **y < x).count());
+                }
+            }
+            assert_eq!(list.as_slice(), model.as_slice());
+        }
+    }
+}
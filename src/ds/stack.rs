@@ -4,12 +4,410 @@
 * take from the top of the Stack
 *
 * Queue
-* Fifo - first in last out
+* Fifo - first in first out
+* insert at the back of the Queue
+* take from the front of the Queue
+*
+* Both come in two flavours behind one trait each:
+*
+*   trait   linked                       array backed
+*   Lifo    Stack (singly linked List)   ArrayStack (MyVec)
+*   Fifo    Queue (DList)                ArrayQueue (RingBuffer)
+*
+* every operation is O(1) (amortized for the array ones). The linked ones
+* allocate per item, the array ones keep items next to each other.
+*
+* push/pop/enqueue/dequeue/len are trait methods, not inherent ones, so
+* the trait has to be in scope to call them:
+*
+*   use playground_dsa_rust::ds::stack::prelude::*;
 * */
 
-pub mod linked_list;
-pub use linked_list::*;
+use super::{DList, List, RingBuffer};
+use crate::vector::MyVec;
+
+/// The traits needed to call the stack and queue operations.
+pub mod prelude {
+    pub use super::{Fifo, Lifo};
+}
+
+pub trait Lifo<T> {
+    fn push(&mut self, item: T);
+    fn pop(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn peek_mut(&mut self) -> Option<&mut T>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// From the top down.
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a;
+}
+
+pub trait Fifo<T> {
+    fn enqueue(&mut self, item: T);
+    fn dequeue(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// From the front (next out) to the back.
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a;
+}
+
+pub struct Stack<T> {
+    items: List<T>,
+    len: usize,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack {
+            items: List::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Lifo<T> for Stack<T> {
+    fn push(&mut self, item: T) {
+        self.items.push(item);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let item = self.items.pop()?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.peek()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.items.peek_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+    {
+        self.items.iter()
+    }
+}
+
+pub struct ArrayStack<T> {
+    items: MyVec<T>,
+}
+
+impl<T> ArrayStack<T> {
+    pub fn new() -> Self {
+        ArrayStack {
+            items: MyVec::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArrayStack {
+            items: MyVec::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T> Default for ArrayStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Lifo<T> for ArrayStack<T> {
+    fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.as_slice().last()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.items.as_mut_slice().last_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+    {
+        self.items.as_slice().iter().rev()
+    }
+}
 
 pub struct Queue<T> {
-    head: LinkedList<T>,
+    items: DList<T>,
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Queue {
+            items: DList::new(),
+        }
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Fifo<T> for Queue<T> {
+    fn enqueue(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.items.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+    {
+        self.items.iter()
+    }
+}
+
+pub struct ArrayQueue<T> {
+    items: RingBuffer<T>,
+}
+
+impl<T> ArrayQueue<T> {
+    pub fn new() -> Self {
+        ArrayQueue {
+            items: RingBuffer::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArrayQueue {
+            items: RingBuffer::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T> Default for ArrayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Fifo<T> for ArrayQueue<T> {
+    fn enqueue(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        self.items.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+    {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    // the same checks run against every implementation of a trait
+    fn check_lifo<S: Lifo<i32> + Default>() {
+        let mut stack = S::default();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.peek(), None);
+        for i in 1..=3 {
+            stack.push(i);
+        }
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.peek(), Some(&3));
+        *stack.peek_mut().unwrap() = 30;
+        assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&30, &2, &1]);
+        assert_eq!(stack.pop(), Some(30));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.len(), 0);
+    }
+
+    fn check_fifo<Q: Fifo<i32> + Default>() {
+        let mut queue = Q::default();
+        assert!(queue.is_empty());
+        assert_eq!(queue.dequeue(), None);
+        for i in 1..=3 {
+            queue.enqueue(i);
+        }
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!(queue.dequeue(), Some(1));
+        queue.enqueue(4);
+        assert_eq!(queue.dequeue(), Some(2));
+        assert_eq!(queue.dequeue(), Some(3));
+        assert_eq!(queue.dequeue(), Some(4));
+        assert_eq!(queue.dequeue(), None);
+        // interleaved, so the ring buffer wraps
+        for i in 0..100 {
+            queue.enqueue(i);
+            queue.enqueue(i + 100);
+            // the queue holds 0, 100, 1, 101, 2, 102, ...
+            let expected = if i % 2 == 0 { i / 2 } else { 100 + i / 2 };
+            assert_eq!(queue.dequeue(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn stack() {
+        check_lifo::<Stack<i32>>();
+    }
+
+    #[test]
+    fn array_stack() {
+        check_lifo::<ArrayStack<i32>>();
+        let mut stack = ArrayStack::with_capacity(2);
+        stack.push(1);
+        assert_eq!(stack.peek(), Some(&1));
+    }
+
+    #[test]
+    fn queue() {
+        check_fifo::<Queue<i32>>();
+    }
+
+    #[test]
+    fn array_queue() {
+        check_fifo::<ArrayQueue<i32>>();
+        let mut queue = ArrayQueue::with_capacity(2);
+        queue.enqueue(1);
+        assert_eq!(queue.peek(), Some(&1));
+    }
+
+    #[test]
+    fn default_needs_no_default_items() {
+        // no Default impl on purpose
+        #[derive(Debug, PartialEq)]
+        struct Job(u32);
+
+        fn push_pop<S: Lifo<Job> + Default>() -> Option<Job> {
+            let mut stack = S::default();
+            stack.push(Job(1));
+            stack.pop()
+        }
+        fn enqueue_dequeue<Q: Fifo<Job> + Default>() -> Option<Job> {
+            let mut queue = Q::default();
+            queue.enqueue(Job(2));
+            queue.dequeue()
+        }
+        assert_eq!(push_pop::<Stack<Job>>(), Some(Job(1)));
+        assert_eq!(push_pop::<ArrayStack<Job>>(), Some(Job(1)));
+        assert_eq!(enqueue_dequeue::<Queue<Job>>(), Some(Job(2)));
+        assert_eq!(enqueue_dequeue::<ArrayQueue<Job>>(), Some(Job(2)));
+    }
+
+    #[test]
+    fn implementations_are_interchangeable() {
+        fn drain_lifo(stack: &mut impl Lifo<char>) -> String {
+            std::iter::from_fn(|| stack.pop()).collect()
+        }
+        let mut linked = Stack::new();
+        let mut array = ArrayStack::new();
+        for c in "abc".chars() {
+            linked.push(c);
+            array.push(c);
+        }
+        assert_eq!(drain_lifo(&mut linked), "cba");
+        assert_eq!(drain_lifo(&mut array), "cba");
+    }
+
+    // only the prelude and the types, not super::*
+    mod prelude_only {
+        use crate::ds::stack::{ArrayQueue, Stack, prelude::*};
+
+        #[test]
+        fn prelude_brings_the_operations_in() {
+            let mut stack = Stack::new();
+            stack.push(1);
+            assert_eq!(stack.len(), 1);
+            assert_eq!(stack.pop(), Some(1));
+
+            let mut queue = ArrayQueue::new();
+            queue.enqueue('a');
+            queue.enqueue('b');
+            assert_eq!(queue.dequeue(), Some('a'));
+            assert_eq!(queue.len(), 1);
+        }
+    }
+
+    #[test]
+    fn drops_items() {
+        let item = Rc::new(());
+        {
+            let mut stack = Stack::new();
+            let mut array_stack = ArrayStack::new();
+            let mut queue = Queue::new();
+            let mut array_queue = ArrayQueue::new();
+            for _ in 0..5 {
+                stack.push(item.clone());
+                array_stack.push(item.clone());
+                queue.enqueue(item.clone());
+                array_queue.enqueue(item.clone());
+            }
+            assert_eq!(Rc::strong_count(&item), 21);
+        }
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
 }
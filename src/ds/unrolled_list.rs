@@ -0,0 +1,576 @@
+use std::{
+    fmt::{self, Debug},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+    slice,
+};
+
+/*
+ * UnrolledList
+ * Doubly linked list of small arrays. Each node holds up to B items next
+ * to each other, so walking the list follows one pointer per B items
+ * instead of one per item.
+ *
+ *   head                                        tail
+ *    |                                            |
+ *    v                                            v
+ *   [ a b c d _ _ ] <-> [ e f g _ _ _ ] <-> [ h i _ _ _ _ ]   B = 6
+ *
+ * Every node but the last is kept at least half full:
+ * - insert into a full node splits it, the upper half moves to a new
+ *   node right after it
+ * - remove that leaves a node under half full takes the first item of
+ *   the next node, or merges the next node in if both fit in one
+ *
+ * get/insert/remove walk nodes from the nearer end, O(n/B) plus O(B) to
+ * shift inside the node. push_back/push_front/pop at the ends are O(1)
+ * (O(B) for the shift at the front). Iteration reads whole arrays.
+ * */
+
+type Link<T, const B: usize> = Option<NonNull<Node<T, B>>>;
+
+struct Node<T, const B: usize> {
+    items: [MaybeUninit<T>; B],
+    len: usize,
+    prev: Link<T, B>,
+    next: Link<T, B>,
+}
+
+impl<T, const B: usize> Node<T, B> {
+    fn alloc() -> NonNull<Self> {
+        NonNull::from(Box::leak(Box::new(Node {
+            items: [const { MaybeUninit::uninit() }; B],
+            len: 0,
+            prev: None,
+            next: None,
+        })))
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: the first len slots are initialized
+        unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: as above
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len) }
+    }
+
+    fn insert(&mut self, idx: usize, item: T) {
+        debug_assert!(self.len < B && idx <= self.len);
+        // SAFETY: there is a free slot, the tail moves up by one and the
+        // hole at idx is filled right after
+        unsafe {
+            let at = self.items.as_mut_ptr().add(idx);
+            ptr::copy(at, at.add(1), self.len - idx);
+            (*at).write(item);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, idx: usize) -> T {
+        debug_assert!(idx < self.len);
+        // SAFETY: idx is initialized, after the read its slot is
+        // overwritten by the tail so nothing is dropped twice
+        let item = unsafe {
+            let at = self.items.as_mut_ptr().add(idx);
+            let item = (*at).assume_init_read();
+            ptr::copy(at.add(1), at, self.len - idx - 1);
+            item
+        };
+        self.len -= 1;
+        item
+    }
+
+    // Moves the items from `at` on to the end of `other`.
+    fn move_tail_to(&mut self, at: usize, other: &mut Self) {
+        let count = self.len - at;
+        debug_assert!(other.len + count <= B);
+        // SAFETY: the source slots are initialized and other has room;
+        // they count as moved once len drops
+        unsafe {
+            ptr::copy_nonoverlapping(
+                self.items.as_ptr().add(at),
+                other.items.as_mut_ptr().add(other.len),
+                count,
+            );
+        }
+        self.len = at;
+        other.len += count;
+    }
+}
+
+pub struct UnrolledList<T, const B: usize = 16> {
+    head: Link<T, B>,
+    tail: Link<T, B>,
+    len: usize,
+    // the list owns its nodes, as if they were boxes
+    _boxed: PhantomData<Box<Node<T, B>>>,
+}
+
+// SAFETY: the list owns its items like a Vec, the raw pointers are never
+// shared outside of borrows of the list
+unsafe impl<T: Send, const B: usize> Send for UnrolledList<T, B> {}
+unsafe impl<T: Sync, const B: usize> Sync for UnrolledList<T, B> {}
+
+impl<T, const B: usize> Default for UnrolledList<T, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const B: usize> UnrolledList<T, B> {
+    pub fn new() -> Self {
+        const { assert!(B >= 2, "nodes must hold at least 2 items") };
+        UnrolledList {
+            head: None,
+            tail: None,
+            len: 0,
+            _boxed: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The node holding `index` and the offset inside it, walking from
+    // whichever end is closer. index == len gives the end of the tail.
+    fn locate(&self, index: usize) -> (NonNull<Node<T, B>>, usize) {
+        debug_assert!(index <= self.len && self.len > 0);
+        // SAFETY: every node reached from head or tail belongs to the list
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.head.expect("list is not empty");
+                let mut offset = index;
+                while offset >= (*node.as_ptr()).len {
+                    offset -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).next.expect("index is in bounds");
+                }
+                (node, offset)
+            } else {
+                let mut node = self.tail.expect("list is not empty");
+                let mut start = self.len - (*node.as_ptr()).len;
+                while index < start {
+                    node = (*node.as_ptr()).prev.expect("index is in bounds");
+                    start -= (*node.as_ptr()).len;
+                }
+                (node, index - start)
+            }
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        // SAFETY: &self keeps the node alive and shared
+        Some(unsafe { &(*node.as_ptr()).as_slice()[offset] })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        // SAFETY: &mut self makes the access unique
+        Some(unsafe { &mut (*node.as_ptr()).as_mut_slice()[offset] })
+    }
+
+    // Links a fresh empty node right after `prev`, or at the front.
+    //
+    // SAFETY: prev must be None or a node of this list.
+    unsafe fn link_after(&mut self, prev: Link<T, B>) -> NonNull<Node<T, B>> {
+        let node = Node::alloc();
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).next,
+                None => self.head,
+            };
+            (*node.as_ptr()).prev = prev;
+            (*node.as_ptr()).next = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+        node
+    }
+
+    // SAFETY: node must be an empty node of this list, it is freed here.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T, B>>) {
+        unsafe {
+            let node = Box::from_raw(node.as_ptr());
+            debug_assert_eq!(node.len, 0);
+            match node.prev {
+                Some(prev) => (*prev.as_ptr()).next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next {
+                Some(next) => (*next.as_ptr()).prev = node.prev,
+                None => self.tail = node.prev,
+            }
+        }
+    }
+
+    pub fn push_back(&mut self, item: T) {
+        // SAFETY: tail and the node linked after it belong to this list
+        unsafe {
+            let node = match self.tail {
+                Some(tail) if (*tail.as_ptr()).len < B => tail,
+                // a full tail stays full, appends fill nodes completely
+                tail => self.link_after(tail),
+            };
+            let node = &mut *node.as_ptr();
+            node.insert(node.len, item);
+        }
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, item: T) {
+        self.insert(0, item);
+    }
+
+    /// # Panics
+    ///
+    /// If `index > len`.
+    pub fn insert(&mut self, index: usize, item: T) {
+        assert!(
+            index <= self.len,
+            "index {index} out of bounds for length {}",
+            self.len
+        );
+        if index == self.len {
+            return self.push_back(item);
+        }
+        let (node, offset) = self.locate(index);
+        // SAFETY: node belongs to this list, so does the one split off it
+        unsafe {
+            if (*node.as_ptr()).len < B {
+                (*node.as_ptr()).insert(offset, item);
+            } else {
+                let half = B / 2;
+                let upper = self.link_after(Some(node));
+                let (lower, upper) = (&mut *node.as_ptr(), &mut *upper.as_ptr());
+                lower.move_tail_to(half, upper);
+                if offset <= half {
+                    lower.insert(offset, item);
+                } else {
+                    upper.insert(offset - half, item);
+                }
+            }
+        }
+        self.len += 1;
+    }
+
+    /// None if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        // SAFETY: node and its next belong to this list
+        let item = unsafe {
+            let item = (*node.as_ptr()).remove(offset);
+            self.refill(node);
+            item
+        };
+        self.len -= 1;
+        Some(item)
+    }
+
+    // Restores the half full rule after `node` lost an item.
+    //
+    // SAFETY: node must belong to this list.
+    unsafe fn refill(&mut self, node: NonNull<Node<T, B>>) {
+        unsafe {
+            let current = &mut *node.as_ptr();
+            if current.len == 0 {
+                return self.unlink(node);
+            }
+            let Some(next) = current.next else { return };
+            if current.len >= B / 2 {
+                return;
+            }
+            let next_node = &mut *next.as_ptr();
+            if current.len + next_node.len <= B {
+                next_node.move_tail_to(0, current);
+                self.unlink(next);
+            } else {
+                let first = next_node.remove(0);
+                current.insert(current.len, first);
+            }
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        // SAFETY: the tail belongs to this list and is never empty
+        let item = unsafe {
+            let node = &mut *tail.as_ptr();
+            let item = node.remove(node.len - 1);
+            if node.len == 0 {
+                self.unlink(tail);
+            }
+            item
+        };
+        self.len -= 1;
+        Some(item)
+    }
+
+    pub fn clear(&mut self) {
+        let mut current = self.head.take();
+        self.tail = None;
+        self.len = 0;
+        while let Some(node) = current {
+            // SAFETY: every node is visited once and freed after its items
+            unsafe {
+                let mut node = Box::from_raw(node.as_ptr());
+                current = node.next;
+                ptr::drop_in_place(node.as_mut_slice());
+            }
+        }
+    }
+
+    pub fn iter(&self) -> UnrolledIter<'_, T, B> {
+        UnrolledIter {
+            items: [].iter(),
+            next: self.head,
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+}
+
+impl<T, const B: usize> Drop for UnrolledList<T, B> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+pub struct UnrolledIter<'a, T, const B: usize> {
+    // the rest of the current node, then the nodes from `next` on
+    items: slice::Iter<'a, T>,
+    next: Link<T, B>,
+    remaining: usize,
+    _list: PhantomData<&'a UnrolledList<T, B>>,
+}
+
+impl<'a, T, const B: usize> Iterator for UnrolledIter<'a, T, B> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                self.remaining -= 1;
+                return Some(item);
+            }
+            let node = self.next?;
+            // SAFETY: the list is borrowed for 'a, its nodes stay alive
+            let node = unsafe { &*node.as_ptr() };
+            self.items = node.as_slice().iter();
+            self.next = node.next;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const B: usize> ExactSizeIterator for UnrolledIter<'_, T, B> {}
+
+impl<'a, T, const B: usize> IntoIterator for &'a UnrolledList<T, B> {
+    type Item = &'a T;
+    type IntoIter = UnrolledIter<'a, T, B>;
+    fn into_iter(self) -> UnrolledIter<'a, T, B> {
+        self.iter()
+    }
+}
+
+impl<T, const B: usize> FromIterator<T> for UnrolledList<T, B> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = UnrolledList::new();
+        for item in iter {
+            list.push_back(item);
+        }
+        list
+    }
+}
+
+impl<T: Debug, const B: usize> Debug for UnrolledList<T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::rc::Rc;
+
+    // item counts per node, checking the links and the half full rule
+    fn node_lens<T, const B: usize>(list: &UnrolledList<T, B>) -> Vec<usize> {
+        let mut lens = Vec::new();
+        let mut prev = None;
+        let mut current = list.head;
+        while let Some(node) = current {
+            // SAFETY: every node reached from head belongs to the list, and
+            // &list keeps it alive and shared while we read it
+            let node_ref = unsafe { &*node.as_ptr() };
+            assert_eq!(node_ref.prev, prev);
+            assert!(node_ref.len > 0 && node_ref.len <= B);
+            lens.push(node_ref.len);
+            prev = Some(node);
+            current = node_ref.next;
+        }
+        assert_eq!(list.tail, prev);
+        assert_eq!(lens.iter().sum::<usize>(), list.len());
+        if let Some((_, body)) = lens.split_last() {
+            assert!(body.iter().all(|len| *len >= B / 2), "{lens:?}");
+        }
+        lens
+    }
+
+    #[test]
+    fn push_and_get() {
+        let mut list = UnrolledList::<_, 4>::new();
+        assert!(list.is_empty());
+        assert_eq!(list.get(0), None);
+        for i in 0..10 {
+            list.push_back(i);
+        }
+        assert_eq!(node_lens(&list), [4, 4, 2]);
+        assert_eq!(list.len(), 10);
+        assert_eq!(list.get(0), Some(&0));
+        assert_eq!(list.get(5), Some(&5));
+        assert_eq!(list.get(9), Some(&9));
+        assert_eq!(list.get(10), None);
+        *list.get_mut(7).unwrap() = 70;
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6, 70, 8, 9]
+        );
+        assert_eq!(list.iter().len(), 10);
+        assert_eq!(
+            format!("{:?}", list.iter().take(3).collect::<Vec<_>>()),
+            "[0, 1, 2]"
+        );
+    }
+
+    #[test]
+    fn insert_splits_full_nodes() {
+        let mut list: UnrolledList<_, 4> = (0..8).collect();
+        assert_eq!(node_lens(&list), [4, 4]);
+        list.insert(1, 10);
+        assert_eq!(node_lens(&list), [3, 2, 4]);
+        list.insert(3, 11);
+        assert_eq!(node_lens(&list), [3, 3, 4]);
+        list.push_front(12);
+        assert_eq!(node_lens(&list), [4, 3, 4]);
+        assert_eq!(format!("{list:?}"), "[12, 0, 10, 1, 11, 2, 3, 4, 5, 6, 7]");
+        list.insert(11, 13);
+        assert_eq!(list.get(11), Some(&13));
+    }
+
+    #[test]
+    fn remove_borrows_and_merges() {
+        let mut list: UnrolledList<_, 4> = (0..12).collect();
+        assert_eq!(node_lens(&list), [4, 4, 4]);
+        assert_eq!(list.remove(0), Some(0));
+        assert_eq!(list.remove(0), Some(1));
+        assert_eq!(list.remove(0), Some(2));
+        // the first node took an item from the second
+        assert_eq!(node_lens(&list), [2, 3, 4]);
+        assert_eq!(list.pop_back(), Some(11));
+        assert_eq!(list.pop_back(), Some(10));
+        assert_eq!(list.remove(2), Some(5));
+        assert_eq!(node_lens(&list), [2, 2, 2]);
+        assert_eq!(list.remove(2), Some(6));
+        // the second node merged the third in
+        assert_eq!(node_lens(&list), [2, 3]);
+        assert_eq!(list.remove(20), None);
+        assert_eq!(list.pop_front(), Some(3));
+        assert_eq!(format!("{list:?}"), "[4, 7, 8, 9]");
+        while list.pop_back().is_some() {}
+        assert!(list.head.is_none() && list.tail.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for length 2")]
+    fn insert_past_the_end() {
+        let mut list: UnrolledList<_> = (0..2).collect();
+        list.insert(3, 0);
+    }
+
+    #[test]
+    fn drops_every_item() {
+        let item = Rc::new(());
+        let mut list = UnrolledList::<_, 3>::new();
+        for i in 0..20 {
+            list.insert(i / 2, item.clone());
+        }
+        drop(list.remove(5));
+        drop(list.pop_front());
+        assert_eq!(Rc::strong_count(&item), 19);
+        drop(list);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    fn check_against_vec<const B: usize>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut list = UnrolledList::<u32, B>::new();
+        let mut model = Vec::new();
+        // miri is far too slow for the full run
+        let steps = if cfg!(miri) { 150 } else { 1500 };
+        for step in 0..steps {
+            match rng.gen_range(0, 6) {
+                0 | 1 => {
+                    let at = rng.gen_range(0, model.len() + 1);
+                    list.insert(at, step);
+                    model.insert(at, step);
+                }
+                2 => {
+                    list.push_back(step);
+                    model.push(step);
+                }
+                3 => {
+                    let at = rng.gen_range(0, model.len() + 1);
+                    let expected = (at < model.len()).then(|| model.remove(at));
+                    assert_eq!(list.remove(at), expected);
+                }
+                4 => assert_eq!(list.pop_back(), model.pop()),
+                _ => {
+                    let at = rng.gen_range(0, model.len() + 1);
+                    assert_eq!(list.get(at), model.get(at));
+                }
+            }
+            node_lens(&list);
+        }
+        assert!(list.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn matches_vec() {
+        for seed in 0..5 {
+            check_against_vec::<2>(seed);
+            check_against_vec::<3>(seed);
+            check_against_vec::<8>(seed);
+            check_against_vec::<16>(seed);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * Growth policies
+ * How a dynamic array picks its next capacity when it runs out of room,
+ * and optionally when it should give memory back after removals.
+ *
+ * policy                 capacities after pushes   copies per push
+ * Doubling               4, 8, 16, 32, ...         ~1
+ * OneAndHalf             4, 6, 9, 13, ...          ~2
+ * FixedIncrement(4)      4, 8, 12, 16, ...         ~n / 8 (O(n))
+ * ShrinkOnUnderflow<G>   same as G, and halves once len <= capacity / 4
+ *
+ * Shrinking at a quarter instead of at half leaves a gap (hysteresis),
+ * so a push/pop pair right on the boundary can not grow and shrink
+ * on every call.
+ * */
+
+/// Smallest capacity any policy allocates.
+pub const MIN_CAPACITY: usize = 4;
+
+pub trait GrowthPolicy {
+    /// Capacity to grow to when `required` items no longer fit in
+    /// `capacity`. Must return at least `required`.
+    fn grow(&self, capacity: usize, required: usize) -> usize;
+
+    /// Capacity to shrink to after a removal left `len` items, or `None`
+    /// to keep the current one. Must return at least `len`.
+    fn shrink(&self, _capacity: usize, _len: usize) -> Option<usize> {
+        None
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    fn grow(&self, capacity: usize, required: usize) -> usize {
+        required.max(capacity.saturating_mul(2)).max(MIN_CAPACITY)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OneAndHalf;
+
+impl GrowthPolicy for OneAndHalf {
+    fn grow(&self, capacity: usize, required: usize) -> usize {
+        required
+            .max(capacity.saturating_add(capacity / 2))
+            .max(MIN_CAPACITY)
+    }
+}
+
+/// Grows by the same amount every time, which makes pushes O(n) amortized.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedIncrement(pub usize);
+
+impl GrowthPolicy for FixedIncrement {
+    fn grow(&self, capacity: usize, required: usize) -> usize {
+        required
+            .max(capacity.saturating_add(self.0))
+            .max(MIN_CAPACITY)
+    }
+}
+
+/// Grows like `G` and halves the capacity once the length drops to a
+/// quarter of it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ShrinkOnUnderflow<G = Doubling>(pub G);
+
+impl<G: GrowthPolicy> GrowthPolicy for ShrinkOnUnderflow<G> {
+    fn grow(&self, capacity: usize, required: usize) -> usize {
+        self.0.grow(capacity, required)
+    }
+
+    fn shrink(&self, capacity: usize, len: usize) -> Option<usize> {
+        if capacity > MIN_CAPACITY && len <= capacity / 4 {
+            Some((capacity / 2).max(MIN_CAPACITY))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacities<G: GrowthPolicy>(policy: G, pushes: usize) -> Vec<usize> {
+        let mut caps = vec![];
+        let mut capacity = 0;
+        for len in 1..=pushes {
+            if len > capacity {
+                capacity = policy.grow(capacity, len);
+                caps.push(capacity);
+            }
+        }
+        caps
+    }
+
+    #[test]
+    fn growth_sequences() {
+        assert_eq!(capacities(Doubling, 33), vec![4, 8, 16, 32, 64]);
+        assert_eq!(capacities(OneAndHalf, 20), vec![4, 6, 9, 13, 19, 28]);
+        assert_eq!(capacities(FixedIncrement(5), 20), vec![5, 10, 15, 20]);
+        assert_eq!(
+            capacities(ShrinkOnUnderflow(Doubling), 33),
+            capacities(Doubling, 33)
+        );
+    }
+
+    #[test]
+    fn grow_covers_required() {
+        assert_eq!(Doubling.grow(4, 100), 100);
+        assert_eq!(OneAndHalf.grow(4, 100), 100);
+        assert_eq!(FixedIncrement(0).grow(4, 5), 5);
+        assert_eq!(Doubling.grow(usize::MAX / 2 + 1, usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn shrink_on_underflow_has_hysteresis() {
+        let policy = ShrinkOnUnderflow(Doubling);
+        assert_eq!(Doubling.shrink(64, 0), None);
+        assert_eq!(policy.shrink(64, 17), None);
+        assert_eq!(policy.shrink(64, 16), Some(32));
+        // right after a shrink we are at half, far from both edges
+        assert_eq!(policy.shrink(32, 16), None);
+        assert_eq!(policy.shrink(4, 0), None);
+        assert_eq!(policy.shrink(8, 1), Some(4));
+    }
+}
@@ -1,21 +1,26 @@
 //pub mod linked_list;
 //pub use linked_list::*;
-//pub mod quicksort;
-//pub use quicksort::*;
-//pub mod recursion;
-//pub use recursion::*;
-//pub mod selection_sorting;
-//pub use selection_sorting::*;
-//pub mod bubble_sort;
-//pub use bubble_sort::*;
 pub mod mergesort;
 pub use mergesort::*;
-//pub mod insert_sorting;
-//pub use insert_sorting::*;
 //pub mod array_list;
 //pub use array_list::*;
+pub mod allocator;
+pub use allocator::*;
+pub mod growth;
+pub use growth::*;
 pub mod vector;
 pub use vector::*;
+pub mod small_vec;
+pub use small_vec::*;
+pub mod slot_map;
+pub use slot_map::*;
+pub mod concurrent_vec;
+pub use concurrent_vec::*;
+pub mod bit_vec;
+pub use bit_vec::*;
 
 pub mod ds;
 pub use ds::*;
+
+pub mod algos;
+pub use algos::*;
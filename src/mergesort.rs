@@ -1,23 +1,42 @@
-use std::{cmp::max, mem::ManuallyDrop, ptr};
+use std::{cmp::Ordering, iter::Peekable, ptr};
 
-pub fn zero_copy_merge(x: Vec<i32>, y: Vec<i32>) -> Vec<i32> {
-    let mut x = ManuallyDrop::new(x);
-    let mut y = ManuallyDrop::new(y);
+use crate::algos::instrument;
+
+// moves items out of both vectors instead of copying them, so it works
+// for any Ord item, and takes from x first on ties (stable)
+pub fn zero_copy_merge<T: Ord>(x: Vec<T>, y: Vec<T>) -> Vec<T> {
+    zero_copy_merge_by(x, y, T::cmp)
+}
+
+pub fn zero_copy_merge_by<T, F>(mut x: Vec<T>, mut y: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let m = x.len();
     let n = y.len();
+    // From here on x and y only own their buffers, the items are moved out
+    // below. If cmp panics the items not moved yet leak, they are never
+    // dropped twice.
+    // SAFETY: shrinking the length never exposes uninitialized items.
+    unsafe {
+        x.set_len(0);
+        y.set_len(0);
+    }
     let xp = x.as_ptr();
     let yp = y.as_ptr();
     let mut out = Vec::with_capacity(m + n);
     let mut i = 0;
     let mut j = 0;
 
+    // SAFETY: x[i..m] and y[j..n] are still initialized and read at most once
+    // each, the buffers stay allocated until x and y drop at the end.
     unsafe {
         while i < m && j < n {
             // Compare by reference (no move)
             let a = &*xp.add(i);
             let b = &*yp.add(j);
 
-            if a <= b {
+            if cmp(a, b) != Ordering::Greater {
                 out.push(ptr::read(xp.add(i))); // move from x[i]
                 i += 1;
             } else {
@@ -37,150 +56,272 @@ pub fn zero_copy_merge(x: Vec<i32>, y: Vec<i32>) -> Vec<i32> {
         }
     }
 
-    // We used ptr::read to move elements out. Prevent dropping the originals:
-    unsafe {
-        ManuallyDrop::drop(&mut x); // drops Vec structure (capacity pointer), NOT elements
-        ManuallyDrop::drop(&mut y);
-    }
-
     out
 }
 
+pub fn zero_copy_merge_by_key<T, K, F>(x: Vec<T>, y: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    zero_copy_merge_by(x, y, |a, b| f(a).cmp(&f(b)))
+}
+
 //  this function receives two sorted vectors
 // and merges them into a single sorted vector
-fn merge_not_good(a: Vec<i32>, b: Vec<i32>) -> Vec<i32> {
+pub fn merge_not_good<T: Ord>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    merge_not_good_by(a, b, T::cmp)
+}
+
+pub fn merge_not_good_by<T, F>(mut a: Vec<T>, mut b: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let len = a.len() + b.len();
     //a [1, 3, 5] starts 0...2
     //b [2, 4, 6, 8, 9] starts 0...4
     // merged goes to 0....7 so
-    let mut merged: Vec<i32> = Vec::with_capacity(len);
-    let mut i = a.len();
-    let mut j = b.len();
+    let mut merged: Vec<T> = Vec::with_capacity(len);
     // since we are receivin two sorted vector we can start from
-    // the end of both vectors and compare the last elements
-    while i > 0 && j > 0 {
-        if a[i - 1] > b[j - 1] {
-            merged.push(a[i - 1]);
-            i -= 1;
+    // the end of both vectors and compare the last elements,
+    // pop moves the last one out so nothing is cloned
+    while let (Some(x), Some(y)) = (a.last(), b.last()) {
+        if cmp(x, y) == Ordering::Greater {
+            merged.extend(a.pop());
         } else {
-            merged.push(b[j - 1]);
-            j -= 1;
+            merged.extend(b.pop());
         }
     }
     // if there are remaining elements in a or b we need to add them to merged
-    while i > 0 {
-        merged.push(a[i - 1]);
-        i -= 1;
+    while let Some(x) = a.pop() {
+        merged.push(x);
     }
-    while j > 0 {
-        merged.push(b[j - 1]);
-        j -= 1;
+    while let Some(y) = b.pop() {
+        merged.push(y);
     }
     // since we were pushing the largest elements first we need to reverse the merged vector
     merged.reverse();
     merged
 }
 
+pub fn merge_not_good_by_key<T, K, F>(a: Vec<T>, b: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_not_good_by(a, b, |x, y| f(x).cmp(&f(y)))
+}
+
 // this merge takes 2n time and n space since we are
 // creating a new vector to store the merged result
-fn merge(a: Vec<i32>, b: Vec<i32>) -> Vec<i32> {
-    let len = a.len() + b.len();
-    let mut merged: Vec<i32> = Vec::with_capacity(len);
-    let mut i = 0;
-    let mut j = 0;
-    while i < a.len() && j < b.len() {
-        if a[i] < b[j] {
-            merged.push(a[i]);
-            i += 1;
+pub fn merge<T: Ord>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    merge_by(a, b, T::cmp)
+}
+
+pub fn merge_by<T, F>(a: Vec<T>, b: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut merged: Vec<T> = Vec::with_capacity(a.len() + b.len());
+    // the items are moved out of a and b, peek compares them in place
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if cmp(x, y) == Ordering::Less {
+            merged.extend(a.next());
         } else {
-            merged.push(b[j]);
-            j += 1;
+            merged.extend(b.next());
         }
     }
-    //while i < a.len() {
-    //    merged.push(a[i]);
-    //    i += 1;
-    //}
-    //while j < b.len() {
-    //    merged.push(b[j]);
-    //    j += 1;
-    //}
-    merged.extend_from_slice(&a[i..]);
-    merged.extend_from_slice(&b[j..]);
+    merged.extend(a);
+    merged.extend(b);
     merged
 }
 
+pub fn merge_by_key<T, K, F>(a: Vec<T>, b: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_by(a, b, |x, y| f(x).cmp(&f(y)))
+}
+
 //bad implementation of merge using recursion
 // this implementation is bad because it creates a
-// new vector for each recursive call and it also uses slicing which creates a new vector as well
-fn merge_recursive(a: Vec<i32>, b: Vec<i32>) -> Vec<i32> {
-    if a.is_empty() {
-        return b;
-    }
-    if b.is_empty() {
-        return a;
-    }
-    if a[0] < b[0] {
-        let mut merged = vec![a[0]];
-        merged.extend(merge_recursive(a[1..].to_vec(), b));
-        merged
-    } else {
-        let mut merged = vec![b[0]];
-        merged.extend(merge_recursive(a, b[1..].to_vec()));
-        merged
-    }
+// new vector for each recursive call and remove(0) shifts
+// the whole rest of the vector to take its first item
+pub fn merge_recursive<T: Ord>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    merge_recursive_by(a, b, T::cmp)
 }
 
-fn merge_recursive_optimized(a: Vec<i32>, b: Vec<i32>) -> Vec<i32> {
-    fn helper(a: &[i32], b: &[i32], i: usize, j: usize, merged: &mut Vec<i32>) {
-        // base case
-        if i == a.len() {
-            merged.extend_from_slice(&b[j..]);
-            return;
+pub fn merge_recursive_by<T, F>(a: Vec<T>, b: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn helper<T, F>(mut a: Vec<T>, mut b: Vec<T>, cmp: &mut F) -> Vec<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        if a.is_empty() {
+            return b;
         }
-        if j == b.len() {
-            merged.extend_from_slice(&a[i..]);
-            return;
+        if b.is_empty() {
+            return a;
         }
-        if a[i] < b[j] {
-            merged.push(a[i]);
-            helper(a, b, i + 1, j, merged);
+        if cmp(&a[0], &b[0]) == Ordering::Less {
+            let mut merged = vec![a.remove(0)];
+            merged.extend(helper(a, b, cmp));
+            merged
         } else {
-            merged.push(b[j]);
-            helper(a, b, i, j + 1, merged);
+            let mut merged = vec![b.remove(0)];
+            merged.extend(helper(a, b, cmp));
+            merged
         }
     }
+    helper(a, b, &mut cmp)
+}
+
+pub fn merge_recursive_by_key<T, K, F>(a: Vec<T>, b: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_recursive_by(a, b, |x, y| f(x).cmp(&f(y)))
+}
+
+pub fn merge_recursive_optimized<T: Ord>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    merge_recursive_optimized_by(a, b, T::cmp)
+}
+
+pub fn merge_recursive_optimized_by<T, F>(a: Vec<T>, b: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // the recursion only passes the two iterators and the output along,
+    // every item is moved once
+    fn helper<T, I, F>(a: &mut Peekable<I>, b: &mut Peekable<I>, merged: &mut Vec<T>, cmp: &mut F)
+    where
+        I: Iterator<Item = T>,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let take_a = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) => cmp(x, y) == Ordering::Less,
+            // base case
+            _ => {
+                merged.extend(a);
+                merged.extend(b);
+                return;
+            }
+        };
+        merged.extend(if take_a { a.next() } else { b.next() });
+        helper(a, b, merged, cmp);
+    }
     let mut merged = Vec::with_capacity(a.len() + b.len());
-    helper(&a, &b, 0, 0, &mut merged);
+    helper(
+        &mut a.into_iter().peekable(),
+        &mut b.into_iter().peekable(),
+        &mut merged,
+        &mut cmp,
+    );
     merged
 }
 
-fn merge2(a: &mut [i32], mid: usize) {
+pub fn merge_recursive_optimized_by_key<T, K, F>(a: Vec<T>, b: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_recursive_optimized_by(a, b, |x, y| f(x).cmp(&f(y)))
+}
+
+// merges the sorted runs a[..mid] and a[mid..] using a buffer for the
+// left run only: 2n time, n / 2 space, ties take from the left (stable)
+pub fn merge2<T: Ord>(a: &mut [T], mid: usize) {
+    merge2_by(a, mid, T::cmp);
+}
+
+pub fn merge2_by<T, F>(a: &mut [T], mid: usize, mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let n = a.len();
     if mid == 0 || mid >= n {
         return;
     }
-    let left = a[..mid].to_vec();
-    let mut i = 0;
-    let mut j = mid;
-    let mut k = 0;
+    // the left run is moved (not cloned) into buf, which keeps len 0 so it
+    // never drops the items, it only owns the allocation
+    let mut buf: Vec<T> = Vec::with_capacity(mid);
+    instrument::moved(mid + n);
+    // SAFETY: buf has room for mid items and does not overlap a. From here
+    // on every item lives exactly once, either in a[..hole.dest],
+    // buf[hole.src..hole.end] or a[right..]; the gap a[hole.dest..right] is
+    // always as long as what is left in buf, so writes never hit an unread
+    // right item. If cmp panics, Hole's drop moves the rest of buf into the
+    // gap and a is a permutation of its old items again.
+    unsafe {
+        let base = a.as_mut_ptr();
+        ptr::copy_nonoverlapping(base, buf.as_mut_ptr(), mid);
+        let mut hole = Hole {
+            src: buf.as_ptr(),
+            end: buf.as_ptr().add(mid),
+            dest: base,
+        };
+        let mut right = base.add(mid);
+        let end = base.add(n);
 
-    while i < left.len() && j < n {
-        if left[i] < a[j] {
-            a[k] = left[i];
-            i += 1;
-        } else {
-            a[k] = a[j];
-            j += 1;
+        while hole.src < hole.end && right < end {
+            let take = if cmp(&*right, &*hole.src) == Ordering::Less {
+                let r = right as *const T;
+                right = right.add(1);
+                r
+            } else {
+                let l = hole.src;
+                hole.src = hole.src.add(1);
+                l
+            };
+            ptr::copy_nonoverlapping(take, hole.dest, 1);
+            hole.dest = hole.dest.add(1);
+        }
+        // dropping hole copies the left items that are still in buf, the
+        // right ones are already in place
+    }
+}
+
+pub fn merge2_by_key<T, K, F>(a: &mut [T], mid: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge2_by(a, mid, |x, y| f(x).cmp(&f(y)));
+}
+
+struct Hole<T> {
+    src: *const T,
+    end: *const T,
+    dest: *mut T,
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        // SAFETY: see merge2_by, the gap at dest is exactly end - src long
+        unsafe {
+            let len = self.end.offset_from(self.src) as usize;
+            ptr::copy_nonoverlapping(self.src, self.dest, len);
         }
-        k += 1;
     }
 }
 
 // worst case time complexity is O(n^2) since we are
 // rotating the array for each element in the left half
 // 1. Rotation-based merge (O(1) space, O(n²) worst case)
-fn merge3(a: &mut [i32], mid: usize) {
+pub fn merge3<T: Ord>(a: &mut [T], mid: usize) {
+    merge3_by(a, mid, T::cmp);
+}
+
+pub fn merge3_by<T, F>(a: &mut [T], mid: usize, mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let n = a.len(); // O(1)
     if mid == 0 || mid >= n {
         // O(1)
@@ -195,11 +336,12 @@ fn merge3(a: &mut [i32], mid: usize) {
     //j only moves i the else branch = e <= R where R is the number of elements in the right half
     while i < left_end && j < n {
         //O(1) * (c + e) iterations
-        if a[i] <= a[j] {
+        if cmp(&a[i], &a[j]) != Ordering::Greater {
             // // O(1) × (c + e)
             i += 1; // O(1) × (c)
         } else {
             a[i..=j].rotate_right(1); // O(j-i+1) * (e) in worst case
+            instrument::moved(j - i + 1);
             i += 1; // O(1) × (e)
             left_end += 1;
             j += 1;
@@ -207,58 +349,121 @@ fn merge3(a: &mut [i32], mid: usize) {
     }
 }
 
-// merge_inplace_insertion: binary search + rotate, O(1) space, O(n log n) time
-fn merge_inplace_insertion(a: &mut [i32], mut mid: usize) {
+pub fn merge3_by_key<T, K, F>(a: &mut [T], mid: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge3_by(a, mid, |x, y| f(x).cmp(&f(y)));
+}
+
+// merge_inplace_insertion: binary search + rotate, O(1) space
+// for every left item a binary search finds the block of right items that
+// are smaller and one rotation moves that whole block in front of it, so it
+// makes O(m log n) comparisons for a left run of m items, but the rotations
+// still move O(n²) items in the worst case (alternating runs)
+pub fn merge_inplace_insertion<T: Ord>(a: &mut [T], mid: usize) {
+    merge_inplace_insertion_by(a, mid, T::cmp);
+}
+
+pub fn merge_inplace_insertion_by<T, F>(a: &mut [T], mut mid: usize, mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let n = a.len();
-    if mid == 0 || mid >= n {
-        return;
+    let mut i = 0;
+    // a[..i] is final, a[i..mid] is what is left of the left run, a[mid..]
+    // of the right run
+    while i < mid && mid < n {
+        // right items equal to a[i] stay behind it (stable)
+        let x = &a[i];
+        let j = a[mid..].partition_point(|r| cmp(r, x) == Ordering::Less);
+        if j > 0 {
+            // [x .. left rest | r0 .. rj-1] -> [r0 .. rj-1 | x .. left rest]
+            a[i..mid + j].rotate_right(j);
+            instrument::moved(mid + j - i);
+            mid += j;
+            i += j;
+        }
+        // x is now followed by right items that are not smaller
+        i += 1;
     }
+}
 
-    if mid <= n - mid {
-        // Left is smaller: insert each left[i] into the right run
-        let mut i = 0;
-        while i < mid {
-            let x = a[i];
-            let right = &a[mid..];
-            let j = right.partition_point(|&r| r < x);
-            if j < right.len() {
-                // Rotate: move a[i] to position mid+j
-                // Segment a[i..=mid+j], rotate left by (mid+j-i) puts a[i] at end
-                a[i..=mid + j].rotate_left(mid + j - i);
-                mid += 1; // boundary shifted
-            }
-            i += 1;
-        }
-    } else {
-        // Right is smaller: insert each right[j] into the left run (from the end)
-        let mut j = mid;
-        while j < n {
-            let x = a[j];
-            let left = &a[..mid];
-            let i = left.partition_point(|&l| l <= x);
-            if i < mid {
-                // Rotate: move a[j] to position i
-                a[i..=j].rotate_right(j - i);
-                mid += 1;
+pub fn merge_inplace_insertion_by_key<T, K, F>(a: &mut [T], mid: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_inplace_insertion_by(a, mid, |x, y| f(x).cmp(&f(y)));
+}
+
+// merge sort in place (bottom up): merges runs of width 1, 2, 4, ...
+// with merge2, so no recursion and at most n / 2 extra space per merge
+pub fn mergesort<T: Ord>(a: &mut [T]) {
+    mergesort_by(a, T::cmp);
+}
+
+pub fn mergesort_by<T, F>(a: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+    let mut width = 1;
+    while width < n {
+        for start in (0..n).step_by(2 * width) {
+            let end = n.min(start + 2 * width);
+            if start + width < end {
+                merge2_by(&mut a[start..end], width, &mut cmp);
             }
-            j += 1;
         }
+        width *= 2;
     }
 }
 
-fn mergesort(arr: Vec<i32>) -> Vec<i32> {
-    if arr.len() <= 1 {
-        return arr;
+pub fn mergesort_by_key<T, K, F>(a: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    mergesort_by(a, |x, y| f(x).cmp(&f(y)));
+}
+
+pub fn mergesort_vec<T: Ord>(arr: Vec<T>) -> Vec<T> {
+    mergesort_vec_by(arr, T::cmp)
+}
+
+pub fn mergesort_vec_by<T, F>(arr: Vec<T>, mut cmp: F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn helper<T, F>(mut arr: Vec<T>, cmp: &mut F) -> Vec<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        if arr.len() <= 1 {
+            return arr;
+        }
+        // split the array into two halves
+        let mid = arr.len() / 2;
+        // split_off moves the right half into a new vector, so the items
+        // are never cloned. It is still not efficient: every level of the
+        // recursion allocates a new vector for each half and for the merge
+        let right = arr.split_off(mid);
+        // recursively sort the left and right halves and then merge them
+        let left = helper(arr, cmp);
+        let right = helper(right, cmp);
+        zero_copy_merge_by(left, right, &mut *cmp)
     }
-    // split the array into two halves
-    let mid = arr.len() / 2;
-    // why this is not efficient? because we are creating
-    // new vectors for each half and we are also using slicing which creates new vectors as well
-    // recursively sort the left and right halves and then merge them
-    let left = mergesort(arr[..mid].to_vec());
-    // recursively sort the left and right halves and then merge them
-    let right = mergesort(arr[mid..].to_vec());
-    merge(left, right)
+    helper(arr, &mut cmp)
+}
+
+pub fn mergesort_vec_by_key<T, K, F>(arr: Vec<T>, mut f: F) -> Vec<T>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    mergesort_vec_by(arr, |x, y| f(x).cmp(&f(y)))
 }
 
 // In-place merging challenge.
@@ -266,18 +471,57 @@ fn mergesort(arr: Vec<i32>) -> Vec<i32> {
 // I think in any order lang would be easy
 // 1. In-place merge using insertion (O(1) space, O(n²) worst case)
 // merge3
-//is truly in-place — it takes &mut [i32] and allocates zero auxiliary memory. It uses rotate_right(1) to shift elements,
+//is truly in-place — it takes &mut [T] and allocates zero auxiliary memory. It uses rotate_right(1) to shift elements,
 //which is O(1) space. Trade-off: worst case is O(n²) time because each rotation can shift up to n elements.
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
     #[test]
     fn test_mergesort() {
         let arr: Vec<i32> = vec![5, 2, 9, 1, 5, 6];
-        let sorted = mergesort(arr);
+        let sorted = mergesort_vec(arr.clone());
         let expected: Vec<i32> = vec![1, 2, 5, 5, 6, 9];
         assert_eq!(sorted, expected);
+
+        let mut arr = arr;
+        mergesort(&mut arr);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn mergesort_sorts_records_by_key_stably() {
+        let mut words = vec!["ccc", "a", "bb", "dd", "e", "fff"];
+        mergesort_by_key(&mut words, |w| w.len());
+        assert_eq!(words, ["a", "e", "bb", "dd", "ccc", "fff"]);
+
+        mergesort_by(&mut words, |a, b| b.cmp(a));
+        assert_eq!(words, ["fff", "e", "dd", "ccc", "bb", "a"]);
+    }
+
+    #[test]
+    fn mergesort_vec_moves_records_stably() {
+        // no Clone: split_off and the merge only move the items
+        #[derive(Debug, PartialEq)]
+        struct Task(u8, String);
+        let tasks = |v: &[(u8, &str)]| -> Vec<Task> {
+            v.iter().map(|&(p, n)| Task(p, n.to_string())).collect()
+        };
+
+        let sorted = mergesort_vec_by_key(
+            tasks(&[(2, "b"), (1, "x"), (2, "a"), (1, "y"), (0, "z")]),
+            |t| t.0,
+        );
+        assert_eq!(
+            sorted,
+            tasks(&[(0, "z"), (1, "x"), (1, "y"), (2, "b"), (2, "a")])
+        );
+
+        let sorted = mergesort_vec_by(sorted, |a, b| b.1.cmp(&a.1));
+        let names: Vec<&str> = sorted.iter().map(|t| t.1.as_str()).collect();
+        assert_eq!(names, ["z", "y", "x", "b", "a"]);
     }
 
     #[test]
@@ -316,6 +560,41 @@ mod tests {
         assert_eq!(merged, c);
     }
 
+    type VecMerge<T> = fn(Vec<T>, Vec<T>) -> Vec<T>;
+
+    #[test]
+    fn vec_merges_by_key_move_their_items() {
+        // no Clone: every item is moved into the result
+        #[derive(Debug, PartialEq)]
+        struct Job(u32, &'static str);
+
+        let merges: [VecMerge<Job>; 5] = [
+            |a, b| merge_not_good_by_key(a, b, |j| j.0),
+            |a, b| merge_by_key(a, b, |j| j.0),
+            |a, b| merge_recursive_by_key(a, b, |j| j.0),
+            |a, b| merge_recursive_optimized_by_key(a, b, |j| j.0),
+            |a, b| zero_copy_merge_by_key(a, b, |j| j.0),
+        ];
+        for merge in merges {
+            let a = vec![Job(1, "a"), Job(4, "b")];
+            let b = vec![Job(2, "c"), Job(3, "d"), Job(5, "e")];
+            let merged: Vec<_> = merge(a, b).into_iter().map(|j| j.1).collect();
+            assert_eq!(merged, ["a", "c", "d", "b", "e"]);
+        }
+
+        let merges: [VecMerge<i32>; 5] = [
+            |a, b| merge_not_good_by(a, b, |x, y| y.cmp(x)),
+            |a, b| merge_by(a, b, |x, y| y.cmp(x)),
+            |a, b| merge_recursive_by(a, b, |x, y| y.cmp(x)),
+            |a, b| merge_recursive_optimized_by(a, b, |x, y| y.cmp(x)),
+            |a, b| zero_copy_merge_by(a, b, |x, y| y.cmp(x)),
+        ];
+        for merge in merges {
+            assert_eq!(merge(vec![9, 4, 1], vec![8, 2]), [9, 8, 4, 2, 1]);
+            assert_eq!(merge(vec![], vec![3]), [3]);
+        }
+    }
+
     #[test]
     fn test_merge2() {
         let mut a: Vec<i32> = vec![1, 3, 5, 2, 4, 6, 8, 9];
@@ -324,6 +603,82 @@ mod tests {
         assert_eq!(a, c);
     }
 
+    #[test]
+    fn merge2_copies_back_the_rest_of_the_left_run() {
+        let mut a = vec![5, 6, 7, 1, 2];
+        merge2(&mut a, 3);
+        assert_eq!(a, [1, 2, 5, 6, 7]);
+    }
+
+    type Pair = (i32, char);
+
+    #[test]
+    fn merges_are_stable() {
+        // sorted by the number only, the letter tells the runs apart
+        let left = [(1, 'l'), (2, 'l'), (2, 'l'), (4, 'l')];
+        let right = [(2, 'r'), (3, 'r'), (4, 'r')];
+        let expected = [
+            (1, 'l'),
+            (2, 'l'),
+            (2, 'l'),
+            (2, 'r'),
+            (3, 'r'),
+            (4, 'l'),
+            (4, 'r'),
+        ];
+        let merges: [fn(&mut [Pair], usize); 6] = [
+            |a, mid| merge2_by(a, mid, |x, y| x.0.cmp(&y.0)),
+            |a, mid| merge3_by(a, mid, |x, y| x.0.cmp(&y.0)),
+            |a, mid| merge_inplace_insertion_by(a, mid, |x, y| x.0.cmp(&y.0)),
+            |a, mid| merge2_by_key(a, mid, |x| x.0),
+            |a, mid| merge3_by_key(a, mid, |x| x.0),
+            |a, mid| merge_inplace_insertion_by_key(a, mid, |x| x.0),
+        ];
+        for merge in merges {
+            let mut a = [left.as_slice(), right.as_slice()].concat();
+            merge(&mut a, left.len());
+            assert_eq!(a, expected);
+        }
+    }
+
+    #[test]
+    fn in_place_merges_match_the_model() {
+        let mut rng = StdRng::seed_from_u64(48);
+        for _ in 0..200 {
+            let n = rng.gen_range(0, 40);
+            let mut a: Vec<u8> = (0..n).map(|_| rng.gen_range(0, 10)).collect();
+            let mid = rng.gen_range(0, n + 1);
+            a[..mid].sort();
+            a[mid..].sort();
+            let mut expected = a.clone();
+            expected.sort();
+
+            for merge in [merge2, merge3, merge_inplace_insertion] {
+                let mut got = a.clone();
+                merge(&mut got, mid);
+                assert_eq!(got, expected, "mid {mid} of {a:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn merge2_keeps_every_item_when_cmp_panics() {
+        let mut a: Vec<String> = ["b", "d", "f", "a", "c", "e"].map(String::from).to_vec();
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            merge2_by(&mut a, 3, |x, y| {
+                calls += 1;
+                if calls == 3 {
+                    panic!("cmp gave up");
+                }
+                x.cmp(y)
+            })
+        }));
+        assert!(result.is_err());
+        a.sort();
+        assert_eq!(a, ["a", "b", "c", "d", "e", "f"]);
+    }
+
     #[test]
     fn test_merge3() {
         let mut a: Vec<i32> = vec![1, 3, 5, 2, 4, 6, 8, 9];
@@ -332,7 +687,7 @@ mod tests {
         assert_eq!(a, c);
     }
 
-    #[test ]
+    #[test]
     fn test_zero_copy_merge() {
         let a: Vec<i32> = vec![1, 3, 5];
         let b: Vec<i32> = vec![2, 4, 6, 8, 9];
@@ -341,4 +696,16 @@ mod tests {
         assert_eq!(merged, c);
     }
 
+    #[test]
+    fn zero_copy_merge_drops_every_item_once() {
+        // String owns heap memory, so Miri catches a double drop or a leak
+        let a: Vec<String> = ["a", "c", "e"].map(String::from).to_vec();
+        let b: Vec<String> = ["b", "d"].map(String::from).to_vec();
+        assert_eq!(zero_copy_merge(a, b), ["a", "b", "c", "d", "e"]);
+
+        let a = vec![(1, 'l'), (3, 'l')];
+        let b = vec![(1, 'r'), (2, 'r'), (3, 'r')];
+        let merged = zero_copy_merge_by_key(a, b, |p| p.0);
+        assert_eq!(merged, [(1, 'l'), (1, 'r'), (2, 'r'), (3, 'l'), (3, 'r')]);
+    }
 }
@@ -0,0 +1,342 @@
+use std::{iter::Enumerate, mem, slice};
+
+use crate::vector::MyVec;
+
+/*
+ * SlotMap (generational arena)
+ * Items live in a MyVec of slots and are handed out as keys of
+ * (index, generation). Removing an item bumps the slot generation, so
+ * every key that still points at the old item stops working, and the
+ * slot goes on a free list to be reused by the next insert.
+ *
+ *   index      0          1             2          3
+ *   slots  [ g0: "a" | g2: free(3) | g1: "c" | g1: free(None) ]
+ *                         ^
+ *   free_head = 1 --------+
+ *
+ *   Key { index: 1, generation: 1 }  -> None, the slot was freed since
+ *   Key { index: 2, generation: 1 }  -> Some("c")
+ *
+ * insert/remove/get are O(1), iteration is O(capacity) and skips free
+ * slots. Keys are Copy, so they work as stable handles in graphs and
+ * linked structures without Rc<RefCell>.
+ * */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    index: usize,
+    generation: u32,
+}
+
+impl Key {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+enum Entry<T> {
+    Occupied(T),
+    Vacant { next_free: Option<usize> },
+}
+
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>,
+}
+
+pub struct SlotMap<T> {
+    slots: MyVec<Slot<T>>,
+    free_head: Option<usize>,
+    len: usize,
+}
+
+impl<T> Default for SlotMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SlotMap<T> {
+    pub fn new() -> Self {
+        SlotMap {
+            slots: MyVec::new(),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        SlotMap {
+            slots: MyVec::with_capacity(capacity),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Key {
+        self.len += 1;
+        match self.free_head {
+            Some(index) => {
+                let slot = self.slots.get_mut(index).expect("free list is corrupt");
+                let Entry::Vacant { next_free } = slot.entry else {
+                    unreachable!("free list points at an occupied slot");
+                };
+                self.free_head = next_free;
+                slot.entry = Entry::Occupied(value);
+                Key {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                let index = self.slots.len();
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Entry::Occupied(value),
+                });
+                Key {
+                    index,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        let slot = self.slots.get_mut(key.index)?;
+        if slot.generation != key.generation || matches!(slot.entry, Entry::Vacant { .. }) {
+            return None;
+        }
+        let entry = mem::replace(
+            &mut slot.entry,
+            Entry::Vacant {
+                next_free: self.free_head,
+            },
+        );
+        // wraps after 2^32 reuses of the same slot, at which point a very
+        // old key could match again
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_head = Some(key.index);
+        self.len -= 1;
+        match entry {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant { .. } => unreachable!(),
+        }
+    }
+
+    pub fn contains_key(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        match self.slots.get(key.index)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        match self.slots.get_mut(key.index)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> SlotIter<'_, T> {
+        SlotIter {
+            slots: self.slots.as_slice().iter().enumerate(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> SlotIterMut<'_, T> {
+        SlotIterMut {
+            slots: self.slots.as_mut_slice().iter_mut().enumerate(),
+        }
+    }
+}
+
+pub struct SlotIter<'a, T> {
+    slots: Enumerate<slice::Iter<'a, Slot<T>>>,
+}
+
+impl<'a, T: 'a> Iterator for SlotIter<'a, T> {
+    type Item = (Key, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in self.slots.by_ref() {
+            if let Entry::Occupied(value) = &slot.entry {
+                let key = Key {
+                    index,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+pub struct SlotIterMut<'a, T> {
+    slots: Enumerate<slice::IterMut<'a, Slot<T>>>,
+}
+
+impl<'a, T: 'a> Iterator for SlotIterMut<'a, T> {
+    type Item = (Key, &'a mut T);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in self.slots.by_ref() {
+            if let Entry::Occupied(value) = &mut slot.entry {
+                let key = Key {
+                    index,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn insert_and_get() {
+        let mut map = SlotMap::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(a), Some(&"a"));
+        assert_eq!(map.get(b), Some(&"b"));
+        *map.get_mut(b).unwrap() = "bb";
+        assert_eq!(map.get(b), Some(&"bb"));
+    }
+
+    #[test]
+    fn remove_invalidates_key() {
+        let mut map = SlotMap::new();
+        let a = map.insert(1);
+        assert_eq!(map.remove(a), Some(1));
+        assert!(map.is_empty());
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get_mut(a), None);
+        assert_eq!(map.remove(a), None);
+        assert!(!map.contains_key(a));
+    }
+
+    #[test]
+    fn reuses_freed_slots() {
+        let mut map = SlotMap::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+        let c = map.insert(3);
+        map.remove(a);
+        map.remove(c);
+
+        // last freed goes first
+        let d = map.insert(4);
+        assert_eq!(d.index(), c.index());
+        assert_eq!(d.generation(), c.generation() + 1);
+        let e = map.insert(5);
+        assert_eq!(e.index(), a.index());
+        let f = map.insert(6);
+        assert_eq!(f.index(), 3);
+
+        assert_eq!(map.get(c), None);
+        assert_eq!(map.get(d), Some(&4));
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get(e), Some(&5));
+        assert_eq!(map.get(b), Some(&2));
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn iter_skips_free_slots() {
+        let mut map = SlotMap::with_capacity(4);
+        let keys: Vec<_> = (0..6).map(|i| map.insert(i)).collect();
+        map.remove(keys[1]);
+        map.remove(keys[4]);
+
+        let live: Vec<_> = map.iter().collect();
+        assert_eq!(
+            live,
+            vec![(keys[0], &0), (keys[2], &2), (keys[3], &3), (keys[5], &5)]
+        );
+
+        for (_, value) in map.iter_mut() {
+            *value *= 10;
+        }
+        let values: Vec<_> = map.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, vec![0, 20, 30, 50]);
+    }
+
+    #[test]
+    fn drops_live_values() {
+        let item = Rc::new(());
+        let mut map = SlotMap::new();
+        let a = map.insert(item.clone());
+        map.insert(item.clone());
+        map.insert(item.clone());
+        assert_eq!(Rc::strong_count(&item), 4);
+        drop(map.remove(a));
+        assert_eq!(Rc::strong_count(&item), 3);
+        drop(map);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn graph_with_handles() {
+        struct Node {
+            value: i32,
+            edges: Vec<Key>,
+        }
+
+        let mut graph = SlotMap::new();
+        let a = graph.insert(Node {
+            value: 1,
+            edges: vec![],
+        });
+        let b = graph.insert(Node {
+            value: 2,
+            edges: vec![a],
+        });
+        graph.get_mut(a).unwrap().edges.push(b);
+
+        let sum: i32 = graph
+            .get(a)
+            .unwrap()
+            .edges
+            .iter()
+            .map(|k| graph.get(*k).unwrap().value)
+            .sum();
+        assert_eq!(sum, 2);
+
+        graph.remove(b);
+        let dangling: Vec<_> = graph
+            .get(a)
+            .unwrap()
+            .edges
+            .iter()
+            .map(|k| graph.get(*k).is_some())
+            .collect();
+        assert_eq!(dangling, vec![false]);
+    }
+}
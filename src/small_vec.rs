@@ -0,0 +1,463 @@
+use std::mem::MaybeUninit;
+
+use crate::{
+    growth::{Doubling, GrowthPolicy},
+    vector::{MyVec, TryReserveError, handle_reserve_error},
+};
+
+/*
+ * SmallVec
+ * Keeps up to N items inline (on the stack or inside the owning struct)
+ * and only moves them into a MyVec once the N+1th item arrives.
+ *
+ *  Inline                          Heap (spilled)
+ * +-----+-----+-----+-----+       +-----+-----+
+ * | a   | b   | ?   | ?   |  -->  | ptr | len | --> [a, b, c, d, e, ?, ?, ?]
+ * +-----+-----+-----+-----+       +-----+-----+
+ *   len = 2, N = 4
+ *
+ * It has the same API as MyVec, so it can stand in for one.
+ * push/pop/get are O(1), insert/remove shift the tail in O(n), and the
+ * spill is a one time O(N) move.
+ * It never goes back inline, like the smallvec crate.
+ * */
+
+enum Data<T, const N: usize> {
+    Inline {
+        buf: [MaybeUninit<T>; N],
+        len: usize,
+    },
+    Heap(MyVec<T>),
+}
+
+pub struct SmallVec<T, const N: usize> {
+    data: Data<T, N>,
+}
+
+impl<T, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> SmallVec<T, N> {
+    pub fn new() -> Self {
+        SmallVec {
+            data: Data::Inline {
+                buf: [const { MaybeUninit::uninit() }; N],
+                len: 0,
+            },
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut vec = Self::new();
+        vec.reserve(capacity);
+        vec
+    }
+
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let mut vec = Self::new();
+        vec.try_reserve(capacity)?;
+        Ok(vec)
+    }
+
+    /// True once the items have moved to the heap.
+    pub fn spilled(&self) -> bool {
+        matches!(self.data, Data::Heap(_))
+    }
+
+    pub fn capacity(&self) -> usize {
+        match &self.data {
+            Data::Inline { .. } => N,
+            Data::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve(additional) {
+            handle_reserve_error(e);
+        }
+    }
+
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match &mut self.data {
+            Data::Heap(vec) => vec.try_reserve(additional),
+            Data::Inline { len, .. } => {
+                let required = len
+                    .checked_add(additional)
+                    .ok_or(TryReserveError::CapacityOverflow)?;
+                if required <= N {
+                    return Ok(());
+                }
+                self.spill(Doubling.grow(N, required))
+            }
+        }
+    }
+
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve_exact(additional) {
+            handle_reserve_error(e);
+        }
+    }
+
+    /// Makes room for exactly `additional` more items, spilling to a heap
+    /// block of exactly that size if they do not fit inline.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match &mut self.data {
+            Data::Heap(vec) => vec.try_reserve_exact(additional),
+            Data::Inline { len, .. } => {
+                let required = len
+                    .checked_add(additional)
+                    .ok_or(TryReserveError::CapacityOverflow)?;
+                if required <= N {
+                    return Ok(());
+                }
+                self.spill(required)
+            }
+        }
+    }
+
+    // moves the inline items into a MyVec of at least `capacity`,
+    // on error everything stays inline
+    fn spill(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+        let Data::Inline { buf, len } = &mut self.data else {
+            return Ok(());
+        };
+        let mut heap = MyVec::try_with_capacity(capacity)?;
+        for slot in &buf[..*len] {
+            // SAFETY: the first len slots are initialized, and setting len
+            // to 0 below makes sure they are not read or dropped again
+            heap.push(unsafe { slot.assume_init_read() });
+        }
+        *len = 0;
+        self.data = Data::Heap(heap);
+        Ok(())
+    }
+
+    pub fn push(&mut self, item: T) {
+        if let Data::Inline { len, .. } = &self.data
+            && *len == N
+        {
+            self.reserve(1);
+        }
+        match &mut self.data {
+            Data::Inline { buf, len } => {
+                buf[*len].write(item);
+                *len += 1;
+            }
+            Data::Heap(vec) => vec.push(item),
+        }
+    }
+
+    /// Like [`SmallVec::push`] but reports allocation failures instead of
+    /// aborting. On error the item is dropped and the vector is unchanged.
+    pub fn try_push(&mut self, item: T) -> Result<(), TryReserveError> {
+        if let Data::Inline { len, .. } = &self.data
+            && *len == N
+        {
+            self.try_reserve(1)?;
+        }
+        match &mut self.data {
+            Data::Inline { buf, len } => {
+                buf[*len].write(item);
+                *len += 1;
+                Ok(())
+            }
+            Data::Heap(vec) => vec.try_push(item),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.data {
+            Data::Inline { buf, len } => {
+                if *len == 0 {
+                    return None;
+                }
+                *len -= 1;
+                // SAFETY: the slot at len was initialized and is now out of
+                // bounds, so it will not be read or dropped again
+                Some(unsafe { buf[*len].assume_init_read() })
+            }
+            Data::Heap(vec) => vec.pop(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match &self.data {
+            Data::Inline { buf, len } => {
+                if index >= *len {
+                    return None;
+                }
+                // SAFETY: the first len slots are initialized
+                Some(unsafe { buf[index].assume_init_ref() })
+            }
+            Data::Heap(vec) => vec.get(index),
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(index)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.data {
+            // SAFETY: the first len slots are initialized, MaybeUninit<T>
+            // has the layout of T
+            Data::Inline { buf, len } => unsafe {
+                std::slice::from_raw_parts(buf.as_ptr().cast::<T>(), *len)
+            },
+            Data::Heap(vec) => vec.as_slice(),
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.data {
+            // SAFETY: see as_slice, and &mut self makes the borrow unique
+            Data::Inline { buf, len } => unsafe {
+                std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<T>(), *len)
+            },
+            Data::Heap(vec) => vec.as_mut_slice(),
+        }
+    }
+
+    /// Inserts at `index` shifting everything after it to the right, O(n).
+    /// Spills if the inline buffer is full. Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, item: T) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+        self.reserve(1);
+        match &mut self.data {
+            Data::Inline { buf, len } => {
+                // SAFETY: reserve left room for len + 1 items inline,
+                // ptr::copy handles the overlap, and the hole at index is
+                // filled right after
+                unsafe {
+                    let at = buf.as_mut_ptr().add(index);
+                    std::ptr::copy(at, at.add(1), *len - index);
+                    (*at).write(item);
+                }
+                *len += 1;
+            }
+            Data::Heap(vec) => vec.insert(index, item),
+        }
+    }
+
+    /// Removes the item at `index` shifting everything after it to the
+    /// left, O(n). None if the index is out of bounds. Stays spilled.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        match &mut self.data {
+            Data::Inline { buf, len } => {
+                if index >= *len {
+                    return None;
+                }
+                // SAFETY: index is in bounds; after the read the slot is
+                // overwritten by the tail, so the item is not dropped twice
+                let item = unsafe {
+                    let at = buf.as_mut_ptr().add(index);
+                    let item = (*at).assume_init_read();
+                    std::ptr::copy(at.add(1), at, *len - index - 1);
+                    item
+                };
+                *len -= 1;
+                Some(item)
+            }
+            Data::Heap(vec) => vec.remove(index),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.data {
+            Data::Inline { len, .. } => *len,
+            Data::Heap(vec) => vec.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T, const N: usize> Drop for SmallVec<T, N> {
+    fn drop(&mut self) {
+        // the heap variant is dropped by MyVec
+        if let Data::Inline { buf, len } = &mut self.data {
+            for slot in &mut buf[..*len] {
+                // SAFETY: the first len slots are initialized
+                unsafe { slot.assume_init_drop() };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallVec;
+    use crate::TryReserveError;
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn stays_inline() {
+        let mut vec = SmallVec::<u32, 4>::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.capacity(), 4);
+        for i in 0..4 {
+            vec.push(i);
+        }
+        assert!(!vec.spilled());
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.get(3), Some(&3));
+        assert_eq!(vec.get(4), None);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.len(), 3);
+    }
+
+    #[test]
+    fn spills_to_heap() {
+        let mut vec = SmallVec::<String, 2>::new();
+        vec.push("a".to_string());
+        vec.push("b".to_string());
+        vec.push("c".to_string());
+        assert!(vec.spilled());
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.get(0).map(String::as_str), Some("a"));
+        assert_eq!(vec.get(2).map(String::as_str), Some("c"));
+        assert_eq!(vec.pop().as_deref(), Some("c"));
+        assert_eq!(vec.pop().as_deref(), Some("b"));
+        assert_eq!(vec.pop().as_deref(), Some("a"));
+        assert_eq!(vec.pop(), None);
+        // once spilled it stays on the heap
+        assert!(vec.spilled());
+    }
+
+    #[test]
+    fn zero_inline_capacity() {
+        let mut vec = SmallVec::<u8, 0>::new();
+        assert_eq!(vec.capacity(), 0);
+        vec.push(1);
+        assert!(vec.spilled());
+        assert_eq!(vec.get(0), Some(&1));
+    }
+
+    #[test]
+    fn reserve() {
+        let mut vec = SmallVec::<u64, 8>::with_capacity(8);
+        assert!(!vec.spilled());
+        vec.push(1);
+        vec.reserve(20);
+        assert!(vec.spilled());
+        assert!(vec.capacity() >= 21);
+        assert_eq!(vec.get(0), Some(&1));
+
+        let vec = SmallVec::<u64, 8>::try_with_capacity(usize::MAX);
+        assert!(matches!(vec, Err(TryReserveError::CapacityOverflow)));
+        let mut vec = SmallVec::<u64, 8>::new();
+        vec.push(1);
+        assert_eq!(
+            vec.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert!(!vec.spilled());
+        assert_eq!(vec.try_push(2), Ok(()));
+    }
+
+    #[test]
+    fn drops_inline_and_heap_items() {
+        let item = Rc::new(());
+        let mut vec = SmallVec::<_, 3>::new();
+        vec.push(item.clone());
+        vec.push(item.clone());
+        assert_eq!(Rc::strong_count(&item), 3);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&item), 1);
+
+        let mut vec = SmallVec::<_, 3>::new();
+        for _ in 0..10 {
+            vec.push(item.clone());
+        }
+        drop(vec.pop());
+        assert_eq!(Rc::strong_count(&item), 10);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn spill_moves_without_dropping() {
+        struct Counted<'a>(&'a Cell<usize>);
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut vec = SmallVec::<_, 2>::new();
+        for _ in 0..5 {
+            vec.push(Counted(&drops));
+        }
+        assert_eq!(drops.get(), 0);
+        drop(vec);
+        assert_eq!(drops.get(), 5);
+    }
+
+    // the same edits once inline and once spilled
+    #[test]
+    fn slices_insert_remove() {
+        for spill in [false, true] {
+            let mut vec = SmallVec::<String, 4>::new();
+            if spill {
+                vec.reserve_exact(5);
+                assert_eq!(vec.capacity(), 5);
+            }
+            assert_eq!(vec.spilled(), spill);
+            vec.push("b".to_string());
+            vec.insert(0, "a".to_string());
+            vec.insert(2, "d".to_string());
+            vec.insert(2, "c".to_string());
+            assert_eq!(vec.as_slice(), ["a", "b", "c", "d"]);
+            vec.get_mut(1).unwrap().push('!');
+            vec.as_mut_slice().reverse();
+            assert_eq!(vec.as_slice(), ["d", "c", "b!", "a"]);
+            assert_eq!(vec.get_mut(4), None);
+            assert_eq!(vec.remove(1).as_deref(), Some("c"));
+            assert_eq!(vec.remove(3), None);
+            assert_eq!(vec.remove(2).as_deref(), Some("a"));
+            assert_eq!(vec.as_slice(), ["d", "b!"]);
+            assert_eq!(vec.spilled(), spill);
+        }
+
+        // a full inline buffer spills on insert
+        let mut vec = SmallVec::<u8, 3>::new();
+        vec.reserve_exact(3);
+        assert!(!vec.spilled());
+        for i in [1, 2, 4] {
+            vec.push(i);
+        }
+        vec.insert(2, 3);
+        assert!(vec.spilled());
+        assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+        assert_eq!(
+            vec.try_reserve_exact(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert!(SmallVec::<u8, 3>::new().as_slice().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index out of bounds")]
+    fn insert_past_the_end() {
+        let mut vec = SmallVec::<u8, 3>::new();
+        vec.push(1);
+        vec.insert(2, 0);
+    }
+
+    #[test]
+    fn zst() {
+        let mut vec = SmallVec::<(), 2>::new();
+        for _ in 0..100 {
+            vec.push(());
+        }
+        assert_eq!(vec.len(), 100);
+        assert_eq!(vec.capacity(), usize::MAX);
+    }
+}
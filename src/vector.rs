@@ -1,80 +1,267 @@
 use std::{
-    alloc::{self, alloc, Layout},
-    isize, mem,
+    alloc::{self, Layout},
+    fmt,
     ptr::NonNull,
 };
 
-pub struct MyVec<T> {
+use crate::{
+    allocator::{Allocator, Global},
+    growth::{Doubling, GrowthPolicy},
+};
+
+/// Why a fallible allocation on [`MyVec`] did not succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity does not fit in `isize::MAX` bytes.
+    CapacityOverflow,
+    /// The allocator could not hand out a block for this layout.
+    AllocError { layout: Layout },
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+            TryReserveError::AllocError { layout } => {
+                write!(f, "could not allocate {} bytes", layout.size())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+pub struct MyVec<T, A: Allocator = Global, G: GrowthPolicy = Doubling> {
     ptr: NonNull<T>,
     capacity: usize,
     len: usize,
+    alloc: A,
+    growth: G,
+}
+
+impl<T> Default for MyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> MyVec<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, G: GrowthPolicy> MyVec<T, Global, G> {
+    pub fn with_growth(growth: G) -> Self {
+        Self::with_growth_in(growth, Global)
+    }
+}
+
+impl<T, A: Allocator> MyVec<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_growth_in(Doubling, alloc)
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self::try_with_capacity_in(capacity, alloc).unwrap_or_else(|e| handle_reserve_error(e))
+    }
+
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let mut vec = Self::new_in(alloc);
+        if capacity > vec.capacity {
+            vec.set_capacity(capacity)?;
+        }
+        Ok(vec)
+    }
+}
+
+impl<T, A: Allocator, G: GrowthPolicy> MyVec<T, A, G> {
+    const IS_ZST: bool = std::mem::size_of::<T>() == 0;
+
+    pub fn with_growth_in(growth: G, alloc: A) -> Self {
+        // zero-sized types never need memory, so like std we pretend
+        // the dangling pointer already holds usize::MAX of them
+        let capacity = if Self::IS_ZST { usize::MAX } else { 0 };
         MyVec {
             ptr: NonNull::dangling(),
-            capacity: 0,
+            capacity,
             len: 0,
+            alloc,
+            growth,
         }
     }
 
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn growth_policy(&self) -> &G {
+        &self.growth
+    }
+
     pub fn capacity(&self) -> usize {
         self.capacity
     }
 
-    pub fn push(&mut self, item: T) {
-        assert_ne!(
-            std::mem::size_of::<T>(),
-            0,
-            "Zero-sized types are not supported"
-        );
-        if std::mem::size_of::<T>() == 0 {
-            panic!("Zero-sized types are not supported");
-        }
-
-        if self.capacity == 0 {
-            let layout = Layout::array::<T>(4).expect("Could not allocate");
-            // SAFETY: the layout is hardcoded to be 4* size_of::<T>()
-            // size_of<T> is > 0
-            let ptr = unsafe { alloc::alloc(layout) } as *mut T;
-            let ptr = NonNull::new(ptr).expect("Could not allocate");
-            // SAFETY: ptr is non null
-            // SAFETY: we just allocated this memory for this item
-            unsafe { ptr.as_ptr().write(item) };
-            self.ptr = ptr;
-            self.capacity = 4;
-            self.len = 1;
-        } else if self.len < self.capacity {
-            let offset = self
-                .len
-                .checked_mul(std::mem::size_of::<T>())
-                .expect("Cannot reach memory locatity");
-            assert!(offset < isize::MAX as usize, "Wrapper isize");
-            // add will calculate the correct position
-            // add skip len items
-            // SAFETY: we have allocated enough memory for len + 1 items
-            unsafe { self.ptr.as_ptr().add(self.len).write(item) };
-            self.len += 1;
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve(additional) {
+            handle_reserve_error(e);
+        }
+    }
+
+    /// Makes room for at least `additional` more items, asking the growth
+    /// policy for the new capacity. The default [`Doubling`] grows to 4
+    /// first and then doubles, so pushes stay amortized O(1).
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.capacity {
+            return Ok(());
+        }
+        // ZSTs already have usize::MAX capacity, getting here means overflow
+        if Self::IS_ZST {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        let new_capacity = self.growth.grow(self.capacity, required).max(required);
+        self.set_capacity(new_capacity)
+    }
+
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve_exact(additional) {
+            handle_reserve_error(e);
+        }
+    }
+
+    /// Makes room for exactly `additional` more items, without asking the
+    /// growth policy.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required <= self.capacity {
+            return Ok(());
+        }
+        if Self::IS_ZST {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+        self.set_capacity(required)
+    }
+
+    /// Moves the items into a block of exactly `new_capacity`, which must
+    /// be at least len. Never called for ZSTs.
+    fn set_capacity(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        debug_assert!(!Self::IS_ZST && new_capacity >= self.len);
+        let new_layout =
+            Layout::array::<T>(new_capacity).map_err(|_| TryReserveError::CapacityOverflow)?;
+        // SAFETY: ptr was allocated by self.alloc with the layout of the
+        // old capacity, which was valid when we built it
+        let old_layout = unsafe { Layout::array::<T>(self.capacity).unwrap_unchecked() };
+        let ptr = if self.capacity == 0 {
+            self.alloc.allocate(new_layout)
+        } else if new_capacity == 0 {
+            // SAFETY: see above, len is 0 so nothing is lost
+            unsafe { self.alloc.deallocate(self.ptr.cast(), old_layout) };
+            Ok(NonNull::dangling())
+        } else if new_capacity > self.capacity {
+            // SAFETY: see above
+            unsafe { self.alloc.grow(self.ptr.cast(), old_layout, new_layout) }
         } else {
-            debug_assert!(self.len == self.capacity);
-            let new_capacity = self.capacity.checked_mul(2).expect("Capacity wrapped");
-            let align = std::mem::align_of::<T>();
-            let size = std::mem::size_of::<T>() * self.capacity;
-            size.checked_add(size % align).expect("Can not allocate");
-            let ptr = unsafe {
-                let layout = alloc::Layout::from_size_align_unchecked(size, align);
-                let new_size = std::mem::size_of::<T>() * new_capacity;
-                let ptr = alloc::realloc(self.ptr.as_ptr() as *mut u8, layout, new_size);
-                let ptr = NonNull::new(ptr as *mut T).expect("Error realloc: could not realloc");
-                ptr.as_ptr().add(self.len).write(item);
-                ptr
-            };
-            self.ptr = ptr;
-            self.len += 1;
-            self.capacity = new_capacity;
+            // SAFETY: see above, the new block still fits len items
+            unsafe { self.alloc.shrink(self.ptr.cast(), old_layout, new_layout) }
+        };
+        // on failure the old block is untouched, so the vector stays valid
+        self.ptr = ptr
+            .map_err(|_| TryReserveError::AllocError { layout: new_layout })?
+            .cast();
+        self.capacity = new_capacity;
+        Ok(())
+    }
+
+    pub fn push(&mut self, item: T) {
+        if let Err(e) = self.try_push(item) {
+            handle_reserve_error(e);
+        }
+    }
+
+    /// Like [`MyVec::push`] but reports allocation failures instead of
+    /// aborting. On error the item is dropped and the vector is unchanged.
+    pub fn try_push(&mut self, item: T) -> Result<(), TryReserveError> {
+        if self.len == self.capacity {
+            self.try_reserve(1)?;
         }
+        // SAFETY: we have allocated enough memory for len + 1 items
+        // (for ZSTs the write through the dangling pointer is a no-op)
+        unsafe { self.ptr.as_ptr().add(self.len).write(item) };
+        self.len += 1;
+        Ok(())
     }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the item at len was initialized and is now out of bounds,
+        // so it will not be read or dropped again
+        let item = unsafe { self.ptr.as_ptr().add(self.len).read() };
+        self.shrink_by_policy();
+        Some(item)
+    }
+
+    /// Inserts at `index` shifting everything after it to the right, O(n).
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, item: T) {
+        assert!(index <= self.len, "insertion index out of bounds");
+        self.reserve(1);
+        // SAFETY: there is room for len + 1 items, ptr::copy handles the
+        // overlap, and the hole at index is filled right after
+        unsafe {
+            let at = self.ptr.as_ptr().add(index);
+            std::ptr::copy(at, at.add(1), self.len - index);
+            at.write(item);
+        }
+        self.len += 1;
+    }
+
+    /// Removes the item at `index` shifting everything after it to the
+    /// left, O(n). None if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        // SAFETY: index is in bounds; after the read the slot is
+        // overwritten by the tail, so the item is not dropped twice
+        let item = unsafe {
+            let at = self.ptr.as_ptr().add(index);
+            let item = at.read();
+            std::ptr::copy(at.add(1), at, self.len - index - 1);
+            item
+        };
+        self.len -= 1;
+        self.shrink_by_policy();
+        Some(item)
+    }
+
+    fn shrink_by_policy(&mut self) {
+        if !Self::IS_ZST
+            && let Some(new_capacity) = self.growth.shrink(self.capacity, self.len)
+        {
+            // giving memory back is best effort, keep the old block on error
+            let _ = self.set_capacity(new_capacity.max(self.len));
+        }
+    }
+
     pub fn get(&self, index: usize) -> Option<&T> {
         if index >= self.len {
             return None;
@@ -82,30 +269,121 @@ impl<T> MyVec<T> {
         Some(unsafe { &*self.ptr.as_ptr().add(index) })
     }
 
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(unsafe { &mut *self.ptr.as_ptr().add(index) })
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first len items are initialized, and ptr is dangling
+        // but aligned when len is 0
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: see as_slice, and &mut self makes the borrow unique
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+pub(crate) fn handle_reserve_error(e: TryReserveError) -> ! {
+    match e {
+        TryReserveError::CapacityOverflow => panic!("Capacity overflow"),
+        TryReserveError::AllocError { layout } => alloc::handle_alloc_error(layout),
+    }
 }
 
-impl<T> Drop for MyVec<T> {
+impl<T, A: Allocator, G: GrowthPolicy> Drop for MyVec<T, A, G> {
     fn drop(&mut self) {
-        if self.capacity == 0 {
+        // SAFETY: the first len items are initialized; for ZSTs this only
+        // runs their destructors
+        unsafe {
+            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                self.ptr.as_ptr(),
+                self.len,
+            ));
+        }
+        if Self::IS_ZST || self.capacity == 0 {
             return; // Nothing to deallocate
         }
+        // SAFETY: same allocator and layout we allocated with in set_capacity
         unsafe {
-            std::ptr::drop_in_place(std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len));
-            let layout = alloc::Layout::from_size_align_unchecked(
-                std::mem::size_of::<T>() * self.capacity,
-                std::mem::align_of::<T>(),
-            );
-            alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            let layout = Layout::array::<T>(self.capacity).unwrap_unchecked();
+            self.alloc.deallocate(self.ptr.cast(), layout);
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::MyVec;
+    use crate::{
+        AllocError, AllocEvent, Allocator, CountingAlloc, Doubling, FixedIncrement, Global,
+        GrowthPolicy, MyVec, OneAndHalf, ShrinkOnUnderflow, TryReserveError,
+    };
+    use std::{alloc::Layout, cell::Cell, ptr::NonNull};
+
+    /// Global allocator that starts returning errors on demand.
+    #[derive(Default)]
+    struct FailingAlloc {
+        // allocations left before it starts failing
+        allocs_before_failure: Cell<Option<usize>>,
+    }
+
+    impl FailingAlloc {
+        fn fail_after(&self, allocs: usize) {
+            self.allocs_before_failure.set(Some(allocs));
+        }
+
+        fn stop_failing(&self) {
+            self.allocs_before_failure.set(None);
+        }
+
+        fn should_fail(&self) -> bool {
+            match self.allocs_before_failure.get() {
+                Some(0) => true,
+                Some(left) => {
+                    self.allocs_before_failure.set(Some(left - 1));
+                    false
+                }
+                None => false,
+            }
+        }
+    }
+
+    unsafe impl Allocator for FailingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            if self.should_fail() {
+                return Err(AllocError);
+            }
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            if self.should_fail() {
+                return Err(AllocError);
+            }
+            unsafe { Global.grow(ptr, old_layout, new_layout) }
+        }
+    }
 
     #[test]
     fn it_works() {
@@ -125,4 +403,302 @@ mod tests {
         let value = Some(&5);
         assert_eq!(vec.get(4), value);
     }
+
+    #[test]
+    fn zst_unit() {
+        let mut vec = MyVec::<()>::new();
+        assert_eq!(vec.capacity(), usize::MAX);
+        assert!(vec.is_empty());
+        for _ in 0..1000 {
+            vec.push(());
+        }
+        assert_eq!(vec.len(), 1000);
+        assert_eq!(vec.capacity(), usize::MAX);
+        assert_eq!(vec.get(999), Some(&()));
+        assert_eq!(vec.get(1000), None);
+    }
+
+    thread_local! {
+        static ZST_DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // zero-sized, so the counter has to live outside of the value
+    struct ZstDropCounter;
+
+    impl Drop for ZstDropCounter {
+        fn drop(&mut self) {
+            ZST_DROPS.with(|d| d.set(d.get() + 1));
+        }
+    }
+
+    struct DropCounter<'a>(&'a Cell<usize>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn zst_drop_count() {
+        assert_eq!(std::mem::size_of::<ZstDropCounter>(), 0);
+        ZST_DROPS.with(|d| d.set(0));
+        {
+            let mut vec = MyVec::new();
+            for _ in 0..10 {
+                vec.push(ZstDropCounter);
+            }
+            assert_eq!(vec.len(), 10);
+            assert_eq!(ZST_DROPS.with(|d| d.get()), 0);
+        }
+        assert_eq!(ZST_DROPS.with(|d| d.get()), 10);
+    }
+
+    #[test]
+    fn drop_count() {
+        let drops = Cell::new(0);
+        {
+            let mut vec = MyVec::new();
+            for _ in 0..5 {
+                vec.push(Box::new(DropCounter(&drops)));
+            }
+        }
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn try_with_capacity_overflow() {
+        let vec = MyVec::<u64>::try_with_capacity(usize::MAX);
+        assert_eq!(vec.err(), Some(TryReserveError::CapacityOverflow));
+        let vec = MyVec::<u8>::try_with_capacity(isize::MAX as usize + 1);
+        assert_eq!(vec.err(), Some(TryReserveError::CapacityOverflow));
+    }
+
+    #[test]
+    fn try_with_capacity_alloc_error() {
+        let alloc = FailingAlloc::default();
+        alloc.fail_after(0);
+        let vec = MyVec::<u64, _>::try_with_capacity_in(10, &alloc);
+        let layout = Layout::array::<u64>(10).unwrap();
+        assert_eq!(vec.err(), Some(TryReserveError::AllocError { layout }));
+
+        alloc.stop_failing();
+        let vec = MyVec::<u64, _>::try_with_capacity_in(10, &alloc).unwrap();
+        assert_eq!(vec.capacity(), 10);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn try_push_alloc_error_keeps_items() {
+        let alloc = FailingAlloc::default();
+        let mut vec = MyVec::new_in(&alloc);
+        for i in 0..4 {
+            vec.try_push(i).unwrap();
+        }
+        assert_eq!(vec.capacity(), 4);
+
+        alloc.fail_after(0);
+        let res = vec.try_push(4);
+        alloc.stop_failing();
+        let layout = Layout::array::<i32>(8).unwrap();
+        assert_eq!(res, Err(TryReserveError::AllocError { layout }));
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.get(3), Some(&3));
+
+        vec.try_push(4).unwrap();
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(vec.get(4), Some(&4));
+    }
+
+    #[test]
+    fn try_reserve() {
+        let alloc = FailingAlloc::default();
+        let mut vec = MyVec::<u32, _>::new_in(&alloc);
+        vec.try_reserve(3).unwrap();
+        assert_eq!(vec.capacity(), 4);
+        vec.try_reserve(20).unwrap();
+        assert_eq!(vec.capacity(), 20);
+        assert_eq!(
+            vec.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+
+        alloc.fail_after(1);
+        vec.try_reserve(21).unwrap();
+        assert_eq!(vec.capacity(), 40);
+        let res = vec.try_reserve(41);
+        alloc.stop_failing();
+        assert!(matches!(res, Err(TryReserveError::AllocError { .. })));
+        assert_eq!(vec.capacity(), 40);
+    }
+
+    #[test]
+    fn zst_try_reserve_overflow() {
+        let mut vec = MyVec::<()>::new();
+        assert_eq!(vec.try_reserve(usize::MAX), Ok(()));
+        vec.push(());
+        assert_eq!(
+            vec.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn push_growth_is_4_then_doubling() {
+        let counter = CountingAlloc::new();
+        {
+            let mut vec = MyVec::new_in(&counter);
+            for i in 0..33u64 {
+                vec.push(i);
+            }
+            assert_eq!(vec.capacity(), 64);
+            assert_eq!(counter.stats().bytes_in_use, 64 * 8);
+        }
+        let grown: Vec<_> = counter
+            .events()
+            .into_iter()
+            .map(|e| match e {
+                AllocEvent::Alloc { size } => size / 8,
+                AllocEvent::Realloc { new_size, .. } => new_size / 8,
+                AllocEvent::Dealloc { .. } => 0,
+                AllocEvent::Failed { .. } => panic!("unexpected failure"),
+            })
+            .collect();
+        assert_eq!(grown, vec![4, 8, 16, 32, 64, 0]);
+
+        let stats = counter.stats();
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.reallocations, 4);
+        assert_eq!(stats.deallocations, 1);
+        assert_eq!(stats.bytes_in_use, 0);
+        assert_eq!(stats.peak_bytes_in_use, 64 * 8);
+        // 4 + 8 + 16 + 32 items moved while growing to fit 33
+        assert_eq!(stats.bytes_moved, 60 * 8);
+    }
+
+    #[test]
+    fn zst_never_allocates() {
+        let counter = CountingAlloc::new();
+        let mut vec = MyVec::new_in(&counter);
+        for _ in 0..100 {
+            vec.push(());
+        }
+        drop(vec);
+        assert_eq!(counter.stats(), Default::default());
+    }
+
+    #[test]
+    fn get_mut_and_slices() {
+        let mut vec = MyVec::new();
+        assert!(vec.as_slice().is_empty());
+        for i in 0..5 {
+            vec.push(i);
+        }
+        *vec.get_mut(1).unwrap() = 10;
+        assert_eq!(vec.get_mut(5), None);
+        vec.as_mut_slice()[2] = 20;
+        assert_eq!(vec.as_slice(), &[0, 10, 20, 3, 4]);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut vec = MyVec::new();
+        vec.insert(0, "b".to_string());
+        vec.insert(0, "a".to_string());
+        vec.insert(2, "d".to_string());
+        vec.insert(2, "c".to_string());
+        vec.insert(4, "e".to_string());
+        assert_eq!(vec.as_slice(), &["a", "b", "c", "d", "e"]);
+        assert_eq!(vec.remove(1).as_deref(), Some("b"));
+        assert_eq!(vec.remove(3).as_deref(), Some("e"));
+        assert_eq!(vec.remove(3), None);
+        assert_eq!(vec.as_slice(), &["a", "c", "d"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut vec = MyVec::new();
+        vec.insert(1, 1);
+    }
+
+    #[test]
+    fn reserve_exact() {
+        let mut vec: MyVec<u8, _, _> = MyVec::with_growth(FixedIncrement(3));
+        vec.reserve_exact(2);
+        assert_eq!(vec.capacity(), 2);
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+        assert_eq!(vec.capacity(), 5);
+        vec.reserve_exact(2);
+        assert_eq!(vec.capacity(), 5);
+        assert_eq!(
+            vec.try_reserve_exact(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn pop() {
+        let mut vec = MyVec::new();
+        assert_eq!(vec.pop(), None);
+        vec.push(Box::new(1));
+        vec.push(Box::new(2));
+        assert_eq!(vec.pop(), Some(Box::new(2)));
+        assert_eq!(vec.pop(), Some(Box::new(1)));
+        assert_eq!(vec.pop(), None);
+        assert_eq!(vec.capacity(), 4);
+    }
+
+    fn reallocations<G: GrowthPolicy>(growth: G, pushes: u64) -> (usize, usize) {
+        let counter = CountingAlloc::new();
+        let mut vec = MyVec::with_growth_in(growth, &counter);
+        for i in 0..pushes {
+            vec.push(i);
+        }
+        let stats = counter.stats();
+        (stats.reallocations, stats.bytes_moved / 8)
+    }
+
+    #[test]
+    fn growth_factors_compared() {
+        // (reallocations, items moved) for 1000 pushes
+        assert_eq!(reallocations(Doubling, 1000), (8, 1020));
+        let (reallocs, moved) = reallocations(OneAndHalf, 1000);
+        assert_eq!(reallocs, 14);
+        assert!(moved > 1020 && moved < 3000, "{moved}");
+        let (reallocs, moved) = reallocations(FixedIncrement(16), 1000);
+        assert_eq!(reallocs, 62);
+        assert!(moved > 30_000, "{moved}");
+    }
+
+    #[test]
+    fn shrink_on_underflow() {
+        let counter = CountingAlloc::new();
+        let mut vec = MyVec::with_growth_in(ShrinkOnUnderflow(Doubling), &counter);
+        for i in 0..64u64 {
+            vec.push(i);
+        }
+        assert_eq!(vec.capacity(), 64);
+        while vec.len() > 17 {
+            vec.pop();
+        }
+        assert_eq!(vec.capacity(), 64);
+        vec.pop();
+        assert_eq!(vec.capacity(), 32);
+        // pushing back right after the shrink does not grow again
+        vec.push(16);
+        vec.pop();
+        assert_eq!(vec.capacity(), 32);
+        while vec.pop().is_some() {}
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(counter.stats().bytes_in_use, 4 * 8);
+        assert_eq!(vec.get(0), None);
+        for i in 0..5 {
+            vec.push(i);
+        }
+        assert_eq!(vec.get(4), Some(&4));
+    }
 }
@@ -0,0 +1,61 @@
+//! Allocation counts for the sorts and merges. They need CountingGlobalAlloc
+//! as the #[global_allocator], which the library cannot install for the
+//! crates that use it, so they run in their own test binary.
+
+use playground_dsa_rust::{
+    Counted, CountingGlobalAlloc, bubble_sorting, counting, measure, merge_inplace_insertion_by,
+    merge2_by, merge3_by, sort_stats, sorters,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+#[global_allocator]
+static ALLOC: CountingGlobalAlloc = CountingGlobalAlloc;
+
+#[test]
+fn nested_measures_count_allocations() {
+    let mut v = vec![Counted(3), Counted(2), Counted(1)];
+    let ((), stats) = measure(|| bubble_sorting(&mut v));
+    assert_eq!(stats.allocations, 0);
+
+    // the clone allocates once, and the inner counts land in the outer
+    let (inner, outer) = measure(|| measure(|| v.clone()).1);
+    assert_eq!(inner.allocations, 1);
+    assert_eq!(outer.allocations, 1);
+    assert_eq!(outer.allocated_bytes, 3 * size_of::<Counted<i32>>());
+}
+
+#[test]
+fn only_merge2_allocates() {
+    let n = if cfg!(miri) { 64 } else { 1024 };
+    // [0, 2, 4, ..] ++ [1, 3, 5, ..]
+    let interleaved: Vec<usize> = (0..n).step_by(2).chain((1..n).step_by(2)).collect();
+
+    let mut v = interleaved.clone();
+    let (_, buffered) = measure(|| merge2_by(&mut v, n / 2, counting(usize::cmp)));
+    let mut v = interleaved.clone();
+    let (_, rotated) = measure(|| merge3_by(&mut v, n / 2, counting(usize::cmp)));
+    let mut v = interleaved;
+    let (_, inserted) = measure(|| merge_inplace_insertion_by(&mut v, n / 2, counting(usize::cmp)));
+
+    assert_eq!(buffered.allocations, 1);
+    assert_eq!(buffered.allocated_bytes, n / 2 * size_of::<usize>());
+    assert_eq!(rotated.allocations, 0);
+    assert_eq!(inserted.allocations, 0);
+}
+
+#[test]
+fn in_place_sorters_do_not_allocate() {
+    let n = if cfg!(miri) { 128 } else { 1024 };
+    let mut rng = StdRng::seed_from_u64(50);
+    let input: Vec<u32> = (0..n).map(|_| rng.r#gen()).collect();
+
+    for sorter in sorters::<Counted<u32>>() {
+        let stats = sort_stats(sorter.as_ref(), &input);
+        assert_eq!(
+            stats.allocations == 0,
+            sorter.is_in_place(),
+            "{}: {stats}",
+            sorter.name()
+        );
+    }
+}